@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use md5::Md5;
+use sha2::{Digest as _, Sha256};
+
+use super::Credentials;
+use crate::error;
+use crate::error::Error;
+use crate::random::random_hex;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Algorithm {
+    Md5,
+    Md5Sess,
+    Sha256,
+    Sha256Sess,
+}
+
+impl Algorithm {
+    fn parse(algorithm: &str) -> Option<Algorithm> {
+        match algorithm.to_uppercase().as_str() {
+            "MD5" => Some(Algorithm::Md5),
+            "MD5-SESS" => Some(Algorithm::Md5Sess),
+            "SHA-256" => Some(Algorithm::Sha256),
+            "SHA-256-SESS" => Some(Algorithm::Sha256Sess),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Md5Sess => "MD5-sess",
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha256Sess => "SHA-256-sess",
+        }
+    }
+
+    fn hash(&self, data: &str) -> String {
+        match self {
+            Algorithm::Md5 | Algorithm::Md5Sess => to_hex(&Md5::digest(data.as_bytes())),
+            Algorithm::Sha256 | Algorithm::Sha256Sess => to_hex(&Sha256::digest(data.as_bytes())),
+        }
+    }
+
+    fn is_session(&self) -> bool {
+        matches!(self, Algorithm::Md5Sess | Algorithm::Sha256Sess)
+    }
+}
+
+/// A `WWW-Authenticate: Digest` challenge, RFC 7616.
+#[derive(Debug)]
+pub struct Challenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Algorithm,
+    qop_auth: bool,
+}
+
+impl Challenge {
+    /// Finds the strongest supported Digest challenge among WWW-Authenticate values.
+    /// Challenges that can't be answered, such as ones with an unknown algorithm,
+    /// are skipped.
+    pub fn find(www_authenticate: &[String]) -> Option<Challenge> {
+        let mut challenges = Vec::new();
+        for value in www_authenticate {
+            for challenge in split_challenges(value) {
+                if let Some(params) = strip_scheme(&challenge, "digest") {
+                    match Challenge::parse(params) {
+                        Ok(challenge) => challenges.push(challenge),
+                        Err(why) => log::debug!("Skipping digest challenge: {}", why.message),
+                    }
+                }
+            }
+        }
+        // Prefer SHA-256 over MD5 when the server offers both
+        challenges
+            .sort_by_key(|c| !matches!(c.algorithm, Algorithm::Sha256 | Algorithm::Sha256Sess));
+        challenges.into_iter().next()
+    }
+
+    fn parse(params: &str) -> Result<Challenge, Error> {
+        let params = parse_params(params);
+        let realm = match params.get("realm") {
+            Some(realm) => realm.clone(),
+            None => error!("digest challenge has no realm"),
+        };
+        let nonce = match params.get("nonce") {
+            Some(nonce) => nonce.clone(),
+            None => error!("digest challenge has no nonce"),
+        };
+        let algorithm = match params.get("algorithm") {
+            Some(algorithm) => match Algorithm::parse(algorithm) {
+                Some(algorithm) => algorithm,
+                None => error!(&format!("unsupported digest algorithm {algorithm}")),
+            },
+            None => Algorithm::Md5,
+        };
+        let qop_auth = match params.get("qop") {
+            Some(qop) => {
+                if !qop
+                    .split(',')
+                    .any(|q| q.trim().eq_ignore_ascii_case("auth"))
+                {
+                    error!(&format!("unsupported digest qop {qop}"))
+                }
+                true
+            }
+            None => false,
+        };
+
+        Ok(Challenge {
+            realm,
+            nonce,
+            opaque: params.get("opaque").cloned(),
+            algorithm,
+            qop_auth,
+        })
+    }
+
+    /// The Authorization header value answering the challenge.
+    pub fn authorization(&self, credentials: &Credentials, method: &str, uri: &str) -> String {
+        self.authorization_with_cnonce(credentials, method, uri, &random_hex(32))
+    }
+
+    fn authorization_with_cnonce(
+        &self,
+        credentials: &Credentials,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        let nc = "00000001";
+        let algorithm = &self.algorithm;
+        let mut ha1 = algorithm.hash(&format!(
+            "{}:{}:{}",
+            credentials.user, self.realm, credentials.password
+        ));
+        if algorithm.is_session() {
+            ha1 = algorithm.hash(&format!("{ha1}:{}:{cnonce}", self.nonce));
+        }
+        let ha2 = algorithm.hash(&format!("{method}:{uri}"));
+        let response = match self.qop_auth {
+            true => algorithm.hash(&format!("{ha1}:{}:{nc}:{cnonce}:auth:{ha2}", self.nonce)),
+            false => algorithm.hash(&format!("{ha1}:{}:{ha2}", self.nonce)),
+        };
+
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{uri}\", algorithm={}, response=\"{response}\"",
+            quote(&credentials.user),
+            quote(&self.realm),
+            quote(&self.nonce),
+            algorithm.name(),
+        );
+        if self.qop_auth {
+            header.push_str(&format!(", qop=auth, nc={nc}, cnonce=\"{cnonce}\""));
+        }
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+        }
+        header
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn strip_scheme<'a>(challenge: &'a str, scheme: &str) -> Option<&'a str> {
+    let challenge = challenge.trim();
+    let (name, params) = challenge.split_once(' ').unwrap_or((challenge, ""));
+    match name.eq_ignore_ascii_case(scheme) {
+        true => Some(params),
+        false => None,
+    }
+}
+
+// Splits a header value that may hold several challenges, such as
+// `Basic realm="a", Digest realm="b", nonce="c"`, on commas outside quotes
+// that are followed by a new scheme rather than a parameter.
+fn split_challenges(value: &str) -> Vec<String> {
+    let mut challenges: Vec<String> = Vec::new();
+    for part in split_unquoted(value, ',') {
+        let trimmed = part.trim();
+        let starts_challenge = match trimmed.split_once(' ') {
+            Some((token, _)) => !token.contains('='),
+            None => !trimmed.contains('='),
+        };
+        match challenges.last_mut() {
+            Some(last) if !starts_challenge => {
+                last.push(',');
+                last.push_str(&part);
+            }
+            _ => challenges.push(trimmed.to_string()),
+        }
+    }
+    challenges
+}
+
+fn parse_params(params: &str) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for param in split_unquoted(params, ',') {
+        if let Some((key, value)) = param.split_once('=') {
+            let value = value.trim();
+            let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+                None => value.to_string(),
+            };
+            map.insert(key.trim().to_lowercase(), value);
+        }
+    }
+    map
+}
+
+fn split_unquoted(value: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c == separator && !quoted => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example from RFC 7616 section 3.9.1
+    const CHALLENGE: &str = r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=ALGORITHM, nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+    const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+    fn credentials() -> Credentials {
+        Credentials {
+            user: "Mufasa".to_string(),
+            password: "Circle of Life".to_string(),
+        }
+    }
+
+    fn challenge(algorithm: &str) -> Challenge {
+        Challenge::find(&[CHALLENGE.replace("ALGORITHM", algorithm)]).unwrap()
+    }
+
+    #[test]
+    fn test_md5_response() {
+        let header = challenge("MD5").authorization_with_cnonce(
+            &credentials(),
+            "GET",
+            "/dir/index.html",
+            CNONCE,
+        );
+        assert!(header.contains(r#"response="8ca523f5e9506fed4657c9700eebdbec""#));
+        assert!(header.contains("qop=auth, nc=00000001"));
+        assert!(header.contains(r#"opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#));
+    }
+
+    #[test]
+    fn test_sha256_response() {
+        let header = challenge("SHA-256").authorization_with_cnonce(
+            &credentials(),
+            "GET",
+            "/dir/index.html",
+            CNONCE,
+        );
+        assert!(header.contains(
+            r#"response="753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1""#
+        ));
+        assert!(header.contains("algorithm=SHA-256"));
+    }
+
+    #[test]
+    fn test_find_prefers_sha256() {
+        let values = vec![
+            r#"Basic realm="x", Digest realm="a", nonce="n", algorithm=MD5"#.to_string(),
+            r#"Digest realm="a", nonce="n", algorithm=SHA-256"#.to_string(),
+        ];
+        let challenge = Challenge::find(&values).unwrap();
+        assert_eq!(challenge.algorithm, Algorithm::Sha256);
+        assert!(!challenge.qop_auth);
+    }
+
+    #[test]
+    fn test_no_digest_challenge() {
+        let values = vec![r#"Basic realm="x""#.to_string()];
+        assert!(Challenge::find(&values).is_none());
+    }
+
+    #[test]
+    fn test_unsupported_challenges_are_skipped() {
+        let values = vec![
+            r#"Digest realm="a", nonce="n", algorithm=SHA-512-256"#.to_string(),
+            r#"Digest realm="a", nonce="n", qop="auth-int""#.to_string(),
+            r#"Digest realm="a", nonce="n", algorithm=MD5"#.to_string(),
+        ];
+        let challenge = Challenge::find(&values).unwrap();
+        assert_eq!(challenge.algorithm, Algorithm::Md5);
+
+        assert!(Challenge::find(&values[..2]).is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+use crate::error;
+use crate::error::Error;
+
+pub mod digest;
+pub mod netrc;
+pub mod oauth2;
+pub mod sigv4;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthType {
+    Basic,
+    Digest,
+}
+
+#[derive(Clone)]
+pub struct Credentials {
+    pub user: String,
+    pub password: String,
+}
+
+impl Credentials {
+    /// Parses `user:password`, prompting for the password on the terminal if omitted.
+    pub fn parse(user: &str) -> Result<Self, Error> {
+        match user.split_once(':') {
+            Some((user, password)) => Ok(Credentials {
+                user: user.to_string(),
+                password: password.to_string(),
+            }),
+            None => {
+                let password = match rpassword::prompt_password(format!("Password for {user}: ")) {
+                    Ok(password) => password,
+                    Err(why) => error!(&format!("can't read password: {why}")),
+                };
+                Ok(Credentials {
+                    user: user.to_string(),
+                    password,
+                })
+            }
+        }
+    }
+
+    pub fn basic(&self) -> String {
+        let encoded = STANDARD.encode(format!("{}:{}", self.user, self.password));
+        format!("Basic {encoded}")
+    }
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("user", &self.user)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic() {
+        let credentials = Credentials::parse("Aladdin:open sesame").unwrap();
+        assert_eq!(credentials.basic(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+    }
+
+    #[test]
+    fn test_password_with_colon() {
+        let credentials = Credentials::parse("user:pass:word").unwrap();
+        assert_eq!(credentials.user, "user");
+        assert_eq!(credentials.password, "pass:word");
+    }
+}
@@ -0,0 +1,241 @@
+use std::path::PathBuf;
+
+use super::Credentials;
+use crate::error;
+use crate::error::Error;
+use crate::io::read_file;
+
+#[derive(Debug, Default)]
+struct Machine {
+    login: Option<String>,
+    password: Option<String>,
+}
+
+/// Credentials from a `.netrc` file, matched on the request host.
+#[derive(Debug, Default)]
+pub struct Netrc {
+    machines: Vec<(String, Machine)>,
+    default: Option<Machine>,
+}
+
+impl Netrc {
+    /// Loads `$HOME/.netrc`, or nothing if there is no such file. It is read for
+    /// every request, so tokens it doesn't understand are skipped instead of failing,
+    /// and its default entry is ignored so that it isn't sent to every host.
+    pub fn load_default() -> Result<Netrc, Error> {
+        let Some(path) = std::env::home_dir().map(|home| home.join(".netrc")) else {
+            return Ok(Netrc::default());
+        };
+        if !path.exists() {
+            return Ok(Netrc::default());
+        }
+        Netrc::read(&path, false)
+    }
+
+    /// Loads a netrc file given by the user, which has to be valid.
+    pub fn load(path: &PathBuf, optional: bool) -> Result<Netrc, Error> {
+        if !path.exists() {
+            if optional {
+                return Ok(Netrc::default());
+            }
+            error!(&format!("netrc file {} does not exist", path.display()))
+        }
+        Netrc::read(path, true)
+    }
+
+    // Only a file given by the user is strict and has its default entry used
+    fn read(path: &PathBuf, explicit: bool) -> Result<Netrc, Error> {
+        log::debug!("Reading netrc file {}", path.display());
+        let mut netrc = Netrc::parse(&read_file(path)?, explicit)?;
+        if !explicit && netrc.default.take().is_some() {
+            log::debug!(
+                "Ignoring the default entry in {}, give it with --netrc-file to use it",
+                path.display()
+            );
+        }
+        Ok(netrc)
+    }
+
+    // A strict parse fails on invalid content, otherwise it is skipped
+    fn parse(content: &str, strict: bool) -> Result<Netrc, Error> {
+        let mut netrc = Netrc::default();
+        let mut tokens = tokenize(content).into_iter();
+        // The machine entry that login and password tokens belong to
+        let mut current: Option<&mut Machine> = None;
+
+        while let Some(token) = tokens.next() {
+            match token.as_str() {
+                "machine" => {
+                    let Some(host) = tokens.next() else {
+                        invalid(strict, "netrc machine has no name")?;
+                        break;
+                    };
+                    netrc
+                        .machines
+                        .push((host.to_lowercase(), Machine::default()));
+                    current = netrc.machines.last_mut().map(|(_, machine)| machine);
+                }
+                "default" => {
+                    netrc.default = Some(Machine::default());
+                    current = netrc.default.as_mut();
+                }
+                "login" | "password" | "account" => {
+                    let Some(value) = tokens.next() else {
+                        invalid(strict, &format!("netrc {token} has no value"))?;
+                        break;
+                    };
+                    match (&mut current, token.as_str()) {
+                        (Some(machine), "login") => machine.login = Some(value),
+                        (Some(machine), "password") => machine.password = Some(value),
+                        _ => {}
+                    }
+                }
+                "macdef" => {
+                    // Macros run until an empty line, which the tokenizer marks
+                    for token in tokens.by_ref() {
+                        if token.is_empty() {
+                            break;
+                        }
+                    }
+                    current = None;
+                }
+                "" => {}
+                other => invalid(strict, &format!("unexpected netrc token \"{other}\""))?,
+            }
+        }
+        Ok(netrc)
+    }
+
+    /// The credentials for the first machine entry matching `host`, or the default entry.
+    pub fn credentials(&self, host: &str) -> Option<Credentials> {
+        let host = host.to_lowercase();
+        let machine = self
+            .machines
+            .iter()
+            .find(|(name, _)| *name == host)
+            .map(|(_, machine)| machine)
+            .or(self.default.as_ref())?;
+        Some(Credentials {
+            user: machine.login.clone()?,
+            password: machine.password.clone().unwrap_or_default(),
+        })
+    }
+}
+
+fn invalid(strict: bool, message: &str) -> Result<(), Error> {
+    if strict {
+        error!(message)
+    }
+    log::debug!("Skipping invalid netrc content: {message}");
+    Ok(())
+}
+
+// Splits on whitespace, keeping double quoted tokens together. Empty lines
+// become empty tokens since they end macro definitions.
+fn tokenize(content: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            tokens.push(String::new());
+            continue;
+        }
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let mut token = String::new();
+            if c == '"' {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => token.extend(chars.next()),
+                        c => token.push(c),
+                    }
+                }
+            } else {
+                token.push(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    token.push(c);
+                }
+            }
+            tokens.push(token);
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NETRC: &str = r#"
+# Work machines
+machine api.petstore.com login luffy password "meat and bones"
+machine Files.Petstore.com
+    login zoro
+    password swords
+
+macdef init
+cd /pub
+bin
+
+default login anonymous password guest
+"#;
+
+    #[test]
+    fn test_machine_credentials() {
+        let netrc = Netrc::parse(NETRC, true).unwrap();
+        let credentials = netrc.credentials("api.petstore.com").unwrap();
+        assert_eq!(credentials.user, "luffy");
+        assert_eq!(credentials.password, "meat and bones");
+        let credentials = netrc.credentials("files.petstore.com").unwrap();
+        assert_eq!(credentials.user, "zoro");
+        assert_eq!(credentials.password, "swords");
+    }
+
+    #[test]
+    fn test_default_credentials() {
+        let netrc = Netrc::parse(NETRC, true).unwrap();
+        let credentials = netrc.credentials("example.com").unwrap();
+        assert_eq!(credentials.user, "anonymous");
+
+        let netrc = Netrc::parse("machine api.petstore.com login luffy", true).unwrap();
+        assert!(netrc.credentials("example.com").is_none());
+    }
+
+    #[test]
+    fn test_unknown_tokens() {
+        let content = "machine api.petstore.com login luffy port 443 password meat";
+        assert!(Netrc::parse(content, true).is_err());
+
+        let netrc = Netrc::parse(content, false).unwrap();
+        let credentials = netrc.credentials("api.petstore.com").unwrap();
+        assert_eq!(credentials.user, "luffy");
+        assert_eq!(credentials.password, "meat");
+    }
+
+    #[test]
+    fn test_implicit_file_has_no_default() {
+        let path = std::env::temp_dir().join("hur_netrc_test");
+        std::fs::write(&path, NETRC).unwrap();
+
+        let netrc = Netrc::read(&path, false).unwrap();
+        assert!(netrc.credentials("api.petstore.com").is_some());
+        assert!(netrc.credentials("example.com").is_none());
+
+        let netrc = Netrc::load(&path, false).unwrap();
+        assert!(netrc.credentials("example.com").is_some());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let path = PathBuf::from("/nonexistent/.netrc");
+        assert!(Netrc::load(&path, true).is_ok());
+        assert!(Netrc::load(&path, false).is_err());
+    }
+}
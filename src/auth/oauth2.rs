@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::Credentials;
+use crate::error;
+use crate::error::Error;
+use crate::http::Method;
+use crate::http::headers::Headers;
+use crate::http::request::Request;
+use crate::io::{read_file, write_private_file};
+use crate::requester::Requester;
+
+// Tokens are refreshed a bit before they expire, to not expire mid request
+const EXPIRY_MARGIN: u64 = 30;
+
+/// OAuth2 client credentials grant, RFC 6749 section 4.4.
+pub struct ClientCredentials {
+    pub token_url: Url,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    token_type: Option<String>,
+    expires_in: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: u64,
+}
+
+impl ClientCredentials {
+    /// Returns a cached access token if it has not expired, otherwise requests a new
+    /// one from the token endpoint and caches it in `cache_path`.
+    pub fn access_token(
+        &self,
+        requester: &mut Requester,
+        cache_path: &PathBuf,
+        timeout: Option<u64>,
+    ) -> Result<String, Error> {
+        let mut cache = load_cache(cache_path);
+        let key = self.cache_key();
+        if let Some(token) = cache.get(&key)
+            && token.expires_at > now()
+        {
+            log::debug!("Using cached OAuth2 token for {}", self.client_id);
+            return Ok(token.access_token.clone());
+        }
+
+        let token = self.request_token(requester, timeout)?;
+        if let Some(expires_in) = token.expires_in {
+            cache.retain(|_, token| token.expires_at > now());
+            cache.insert(
+                key,
+                CachedToken {
+                    access_token: token.access_token.clone(),
+                    expires_at: now() + expires_in.saturating_sub(EXPIRY_MARGIN),
+                },
+            );
+            write_private_file(cache_path, serde_json::to_string_pretty(&cache)?.as_bytes())?;
+        }
+        Ok(token.access_token)
+    }
+
+    fn request_token(
+        &self,
+        requester: &mut Requester,
+        timeout: Option<u64>,
+    ) -> Result<TokenResponse, Error> {
+        log::debug!("Requesting OAuth2 token from {}", self.token_url);
+        let mut form = url::form_urlencoded::Serializer::new(String::new());
+        form.append_pair("grant_type", "client_credentials");
+        if let Some(scope) = &self.scope {
+            form.append_pair("scope", scope);
+        }
+
+        let credentials = Credentials {
+            user: form_encode(&self.client_id),
+            password: form_encode(&self.client_secret),
+        };
+        let mut headers = Headers::new();
+        headers.add("Authorization", &credentials.basic());
+        headers.add("Content-Type", "application/x-www-form-urlencoded");
+        headers.add("Accept", "application/json");
+        let request = Request::with_body(
+            self.token_url.clone(),
+            Method::Post,
+            headers,
+            form.finish(),
+            timeout,
+        )?;
+
+        let response = requester.do_request(request)?;
+        let body = response.body.unwrap_or_default();
+        if !(200..=299).contains(&response.status_code) {
+            error!(&format!(
+                "OAuth2 token request failed with status {}: {body}",
+                response.status_code
+            ))
+        }
+        let token: TokenResponse = serde_json::from_str(&body)?;
+        if let Some(token_type) = &token.token_type
+            && !token_type.eq_ignore_ascii_case("bearer")
+        {
+            error!(&format!("unsupported OAuth2 token type {token_type}"))
+        }
+        Ok(token)
+    }
+
+    fn cache_key(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.token_url,
+            self.client_id,
+            self.scope.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+fn load_cache(path: &PathBuf) -> HashMap<String, CachedToken> {
+    read_file(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Client id and secret are form encoded before Basic encoding, RFC 6749 section 2.3.1
+fn form_encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modes::RedirectMode;
+    use crate::requester::connector::RegularConnector;
+    use httptest::{Expectation, Server, matchers::*, responders::*};
+
+    fn client(server: &Server) -> ClientCredentials {
+        ClientCredentials {
+            token_url: Url::parse(&server.url("/token").to_string()).unwrap(),
+            client_id: "hur".to_string(),
+            client_secret: "s3cret".to_string(),
+            scope: Some("read write".to_string()),
+        }
+    }
+
+    fn requester() -> Requester {
+        Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::NoFollow)
+    }
+
+    #[test]
+    fn test_token_is_requested_and_cached() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", "/token"),
+                request::headers(contains(("authorization", "Basic aHVyOnMzY3JldA=="))),
+                request::body("grant_type=client_credentials&scope=read+write"),
+            ])
+            .times(1)
+            .respond_with(json_encoded(serde_json::json!({
+                "access_token": "abc",
+                "token_type": "Bearer",
+                "expires_in": 3600
+            }))),
+        );
+        let cache_path = std::env::temp_dir().join("hur_oauth2_cache_test.json");
+        let _ = std::fs::remove_file(&cache_path);
+
+        let client = client(&server);
+        let token = client
+            .access_token(&mut requester(), &cache_path, None)
+            .unwrap();
+        let cached = client
+            .access_token(&mut requester(), &cache_path, None)
+            .unwrap();
+
+        assert_eq!(token, "abc");
+        assert_eq!(cached, "abc");
+        let _ = std::fs::remove_file(&cache_path);
+    }
+
+    #[test]
+    fn test_token_error() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("POST", "/token"))
+                .respond_with(status_code(401).body(r#"{"error":"invalid_client"}"#)),
+        );
+        let cache_path = std::env::temp_dir().join("hur_oauth2_error_test.json");
+
+        let result = client(&server).access_token(&mut requester(), &cache_path, None);
+
+        assert!(result.unwrap_err().message.contains("invalid_client"));
+    }
+}
@@ -0,0 +1,397 @@
+use std::time::SystemTime;
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::date::format_iso8601_basic;
+use crate::error;
+use crate::error::Error;
+use crate::http::headers::Headers;
+use crate::http::redact::{REDACTED, redaction};
+use crate::http::request::Request;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Signing parameters from `--aws-sigv4 provider:region:service`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigningConfig {
+    pub provider: String,
+    pub region: String,
+    pub service: String,
+}
+
+impl SigningConfig {
+    pub fn try_from(value: &str) -> Result<Self, Error> {
+        let splits: Vec<&str> = value.split(':').collect();
+        match splits.as_slice() {
+            [provider, region, service]
+                if !provider.is_empty() && !region.is_empty() && !service.is_empty() =>
+            {
+                Ok(SigningConfig {
+                    provider: provider.to_lowercase(),
+                    region: region.to_string(),
+                    service: service.to_string(),
+                })
+            }
+            _ => error!(&format!(
+                "invalid signing config \"{value}\", expected provider:region:service"
+            )),
+        }
+    }
+
+    fn algorithm(&self) -> String {
+        format!("{}4-HMAC-SHA256", self.provider.to_uppercase())
+    }
+
+    // AWS uses x-amz-date rather than x-aws-date
+    fn header_prefix(&self) -> String {
+        match self.provider.as_str() {
+            "aws" => "x-amz".to_string(),
+            provider => format!("x-{provider}"),
+        }
+    }
+
+    fn scope(&self, date: &str) -> String {
+        format!(
+            "{date}/{}/{}/{}4_request",
+            self.region, self.service, self.provider
+        )
+    }
+}
+
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn from_env() -> Result<Self, Error> {
+        let access_key_id = match std::env::var("AWS_ACCESS_KEY_ID") {
+            Ok(key) => key,
+            Err(_) => error!("AWS_ACCESS_KEY_ID is not set"),
+        };
+        let secret_access_key = match std::env::var("AWS_SECRET_ACCESS_KEY") {
+            Ok(key) => key,
+            Err(_) => error!("AWS_SECRET_ACCESS_KEY is not set"),
+        };
+        Ok(AwsCredentials {
+            access_key_id,
+            secret_access_key,
+            session_token: std::env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+/// Signs the request by adding the date, security token, payload hash and
+/// Authorization headers. Must run after all other headers and the body are set.
+pub fn sign(
+    request: &mut Request,
+    config: &SigningConfig,
+    credentials: &AwsCredentials,
+    time: SystemTime,
+) -> Result<(), Error> {
+    let prefix = config.header_prefix();
+    let timestamp = format_iso8601_basic(time);
+    // File bodies are streamed through the hash rather than read into memory,
+    // while streams can only be read once and are sent unsigned
+    let payload_hash = match request.body() {
+        Some(body) if !body.is_replayable() => UNSIGNED_PAYLOAD.to_string(),
+        Some(body) => {
+            let mut hasher = Sha256::new();
+            body.write_to(&mut hasher)?;
+            to_hex(&hasher.finalize())
+        }
+        None => hex_sha256(b""),
+    };
+
+    request.headers.remove("Authorization");
+    request.headers.remove(&format!("{prefix}-date"));
+    request.headers.add(&format!("{prefix}-date"), &timestamp);
+    if let Some(token) = &credentials.session_token {
+        request.headers.remove(&format!("{prefix}-security-token"));
+        request
+            .headers
+            .add_secret(&format!("{prefix}-security-token"), token);
+    }
+    if config.service == "s3"
+        && request
+            .headers
+            .get(&format!("{prefix}-content-sha256"))
+            .is_none()
+    {
+        request
+            .headers
+            .add(&format!("{prefix}-content-sha256"), &payload_hash);
+    }
+
+    let authorization = authorization(
+        config,
+        credentials,
+        &timestamp,
+        &request.method.to_string().to_uppercase(),
+        request.url.path(),
+        request.url.query().unwrap_or_default(),
+        &request.headers,
+        &payload_hash,
+    );
+    request.headers.add("Authorization", &authorization);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn authorization(
+    config: &SigningConfig,
+    credentials: &AwsCredentials,
+    timestamp: &str,
+    method: &str,
+    path: &str,
+    query: &str,
+    headers: &Headers,
+    payload_hash: &str,
+) -> String {
+    let (canonical_headers, signed_headers) = canonical_headers(headers, &config.header_prefix());
+    let canonical_request = format!(
+        "{method}\n{}\n{}\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+        canonical_uri(path, config.service == "s3"),
+        canonical_query(query),
+    );
+    log::debug!(
+        "Canonical request:\n{}",
+        redact_canonical_headers(&canonical_request, &canonical_headers)
+    );
+
+    let date = &timestamp[..8];
+    let scope = config.scope(date);
+    let string_to_sign = format!(
+        "{}\n{timestamp}\n{scope}\n{}",
+        config.algorithm(),
+        hex_sha256(canonical_request.as_bytes())
+    );
+
+    let secret = format!(
+        "{}4{}",
+        config.provider.to_uppercase(),
+        credentials.secret_access_key
+    );
+    let mut key = hmac(secret.as_bytes(), date.as_bytes());
+    for part in [
+        config.region.as_str(),
+        config.service.as_str(),
+        &format!("{}4_request", config.provider),
+    ] {
+        key = hmac(&key, part.as_bytes());
+    }
+    let signature = to_hex(&hmac(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "{} Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        config.algorithm(),
+        credentials.access_key_id,
+    )
+}
+
+// Only headers that are not changed on the way to the server are signed
+fn canonical_headers(headers: &Headers, prefix: &str) -> (String, String) {
+    let mut signed: Vec<(String, String)> = headers
+        .iter()
+        .filter(|(key, _)| {
+            matches!(key.as_str(), "host" | "content-type" | "content-md5")
+                || key.starts_with(&format!("{prefix}-"))
+        })
+        .map(|(key, values)| {
+            let values: Vec<String> = values
+                .iter()
+                .map(|v| v.split_whitespace().collect::<Vec<&str>>().join(" "))
+                .collect();
+            (key.to_lowercase(), values.join(","))
+        })
+        .collect();
+    signed.sort();
+
+    let canonical = signed
+        .iter()
+        .map(|(key, value)| format!("{key}:{value}\n"))
+        .collect();
+    let names: Vec<&str> = signed.iter().map(|(key, _)| key.as_str()).collect();
+    (canonical, names.join(";"))
+}
+
+// The canonical request holds header values such as the session token
+fn redact_canonical_headers(canonical_request: &str, canonical_headers: &str) -> String {
+    let redaction = redaction();
+    let redacted: String = canonical_headers
+        .lines()
+        .map(|line| match line.split_once(':') {
+            Some((key, _)) if redaction.is_redacted(key, false) => format!("{key}:{REDACTED}\n"),
+            _ => format!("{line}\n"),
+        })
+        .collect();
+    canonical_request.replacen(canonical_headers, &redacted, 1)
+}
+
+fn canonical_uri(path: &str, s3: bool) -> String {
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    path.split('/')
+        .map(|segment| {
+            let encoded = uri_encode(&percent_decode(segment));
+            match s3 {
+                true => encoded,
+                false => uri_encode(&encoded),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn canonical_query(query: &str) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (
+                uri_encode(&percent_decode(key)),
+                uri_encode(&percent_decode(value)),
+            )
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+fn uri_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && let Ok(hex) = std::str::from_utf8(&bytes[i + 1..i + 3])
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the AWS Signature Version 4 test suite
+    fn credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        }
+    }
+
+    fn config() -> SigningConfig {
+        SigningConfig::try_from("aws:us-east-1:service").unwrap()
+    }
+
+    fn headers() -> Headers {
+        let mut headers = Headers::new();
+        headers.add("Host", "example.amazonaws.com");
+        headers.add("X-Amz-Date", "20150830T123600Z");
+        headers.add("User-Agent", "hur/0.1.0");
+        headers
+    }
+
+    #[test]
+    fn test_get_vanilla() {
+        let authorization = authorization(
+            &config(),
+            &credentials(),
+            "20150830T123600Z",
+            "GET",
+            "/",
+            "",
+            &headers(),
+            &hex_sha256(b""),
+        );
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_get_vanilla_query_order() {
+        let authorization = authorization(
+            &config(),
+            &credentials(),
+            "20150830T123600Z",
+            "GET",
+            "/",
+            "Param2=value2&Param1=value1",
+            &headers(),
+            &hex_sha256(b""),
+        );
+        assert!(authorization.ends_with(
+            "Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        ));
+    }
+
+    #[test]
+    fn test_canonical_uri() {
+        assert_eq!(canonical_uri("/", false), "/");
+        assert_eq!(canonical_uri("/a b/c", true), "/a%20b/c");
+        assert_eq!(canonical_uri("/a%20b", false), "/a%2520b");
+    }
+
+    #[test]
+    fn test_signing_config() {
+        assert_eq!(
+            SigningConfig::try_from("aws:eu-north-1:s3").unwrap(),
+            SigningConfig {
+                provider: "aws".to_string(),
+                region: "eu-north-1".to_string(),
+                service: "s3".to_string(),
+            }
+        );
+        assert!(SigningConfig::try_from("aws:s3").is_err());
+    }
+}
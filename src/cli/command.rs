@@ -1,6 +1,12 @@
 use std::path::PathBuf;
 
+use super::parsing::{FormField, parse_query_param};
+use super::template::Variable;
+use crate::auth::AuthType;
+use crate::http::compression::Compression;
 use crate::http::headers::Header;
+use crate::http::multipart::MultipartField;
+use crate::requester::retry::RetryOn;
 use crate::{http::Method, modes::RedirectMode};
 use clap::{Args, Parser, Subcommand};
 
@@ -27,7 +33,7 @@ pub enum Commands {
         command: Option<ConfigCommands>,
     },
     #[command(about = "Make HTTP requests")]
-    Req(ReqArgs),
+    Req(Box<ReqArgs>),
 }
 
 #[derive(Args)]
@@ -35,6 +41,11 @@ pub enum Commands {
 pub struct ReqArgs {
     #[arg(help = "The URL for the request", required = true)]
     pub url: String,
+    #[arg(
+        value_name = "ITEM",
+        help = "Request items after the URL: 'Header:value', 'param==value', 'field=value', 'field:=json', 'field=@file' or 'field:=@file.json'. Fields build a JSON body and can be nested, like 'owner[name]=Luffy'"
+    )]
+    pub items: Vec<String>,
     #[arg(short, long, help = "Full request and response output in JSON")]
     pub verbose: bool,
     #[arg(
@@ -45,8 +56,38 @@ pub struct ReqArgs {
         default_value_t = Method::Get
     )]
     pub method: Method,
-    #[arg(short = 'h', long, help = "Add header as 'key:value'", value_parser = Header::try_from)]
+    #[arg(
+        long,
+        value_name = "NAME=VALUE",
+        help = "Set a variable for {{NAME}} placeholders in the URL, headers and body",
+        value_parser = Variable::try_from
+    )]
+    pub var: Vec<Variable>,
+    #[arg(long, help = "Read variables from a file of NAME=VALUE lines")]
+    pub env_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Use variables from an [environments.NAME] table in the config file"
+    )]
+    pub env: Option<String>,
+    #[arg(
+        short = 'q',
+        long,
+        value_name = "KEY=VALUE",
+        help = "Add a query parameter, percent-encoded and appended to the query in the URL",
+        value_parser = parse_query_param
+    )]
+    pub query: Vec<(String, String)>,
+    #[arg(
+        short = 'h',
+        long,
+        help = "Add header as 'key:value', or leave out a default header with 'key:'",
+        value_parser = Header::try_from
+    )]
     pub header: Option<Vec<Header>>,
+    #[arg(long, help = "Set the User-Agent header. Default hur/<version>")]
+    pub user_agent: Option<String>,
     #[arg(long, help = "Add headers as a JSON string or JSON file")]
     pub headers_json: Option<String>,
     #[arg(short, long, help = "Add request body")]
@@ -65,6 +106,117 @@ pub struct ReqArgs {
         conflicts_with = "body_file"
     )]
     pub body_json: Option<String>,
+    #[arg(
+        short = 'd',
+        long,
+        help = "Add 'key=value' to a request body sent with Content-Type:application/x-www-form-urlencoded",
+        value_parser = FormField::try_from,
+        conflicts_with_all = ["body", "body_file", "body_json"]
+    )]
+    pub form_urlencoded: Option<Vec<FormField>>,
+    #[arg(
+        short = 'F',
+        long,
+        help = "Add a multipart/form-data field as 'name=value', or a file as 'name=@path;type=mime;filename=name'",
+        value_parser = MultipartField::try_from,
+        conflicts_with_all = ["body", "body_file", "body_json", "form_urlencoded"]
+    )]
+    pub form: Option<Vec<MultipartField>>,
+    #[arg(
+        long,
+        help = "Send the body with Transfer-Encoding:chunked, streaming a body from stdin instead of reading it into memory"
+    )]
+    pub chunked: bool,
+    #[arg(
+        long,
+        help = "Send Expect:100-continue with the body, so the server can reject it before it is sent"
+    )]
+    pub expect_continue: bool,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ENCODING",
+        help = "Compress the body and set Content-Encoding"
+    )]
+    pub compress_body: Option<Compression>,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How long to wait for 100 Continue before sending the body anyway. Default 1"
+    )]
+    pub expect_timeout: Option<f64>,
+    #[arg(
+        long,
+        help = "Send cookies from a Netscape cookie file, or as 'name=value; name2=value2'"
+    )]
+    pub cookie: Option<String>,
+    #[arg(
+        long,
+        help = "Write all cookies to this Netscape cookie file after the request"
+    )]
+    pub cookie_jar: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        help = "Credentials as 'user:password', prompts for the password if omitted"
+    )]
+    pub user: Option<String>,
+    #[arg(
+        long,
+        value_enum,
+        help = "Authentication scheme for --user. Default basic"
+    )]
+    pub auth_type: Option<AuthType>,
+    #[arg(
+        long,
+        help = "Send a bearer token in the Authorization header",
+        conflicts_with_all = ["user", "oauth2_token_url"]
+    )]
+    pub bearer: Option<String>,
+    #[arg(
+        long,
+        help = "Get a bearer token with the OAuth2 client credentials grant from this URL",
+        requires_all = ["client_id", "client_secret"],
+        conflicts_with = "user"
+    )]
+    pub oauth2_token_url: Option<String>,
+    #[arg(long, help = "OAuth2 client id", requires = "oauth2_token_url")]
+    pub client_id: Option<String>,
+    #[arg(long, help = "OAuth2 client secret", requires = "oauth2_token_url")]
+    pub client_secret: Option<String>,
+    #[arg(long, help = "OAuth2 scope to request", requires = "oauth2_token_url")]
+    pub scope: Option<String>,
+    #[arg(
+        long,
+        value_name = "PROVIDER:REGION:SERVICE",
+        help = "Sign the request with AWS Signature Version 4, using credentials from AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY and AWS_SESSION_TOKEN",
+        conflicts_with_all = ["user", "bearer", "oauth2_token_url"]
+    )]
+    pub aws_sigv4: Option<String>,
+    #[arg(
+        long,
+        help = "Read credentials from this netrc file instead of $HOME/.netrc"
+    )]
+    pub netrc_file: Option<PathBuf>,
+    #[arg(long, help = "Don't fail if the netrc file does not exist")]
+    pub netrc_optional: bool,
+    #[arg(
+        long,
+        help = "Use a named session, storing headers, cookies and auth in $HOME/.config/hur/sessions"
+    )]
+    pub session: Option<String>,
+    #[arg(
+        long,
+        value_name = "HEADER",
+        help = "Redact this header in verbose output and debug logs, in addition to Authorization, Cookie and other credential headers"
+    )]
+    pub redact: Vec<String>,
+    #[arg(
+        long,
+        help = "Don't redact any headers in verbose output and debug logs",
+        conflicts_with = "redact"
+    )]
+    pub no_redact: bool,
     #[arg(long, action = clap::ArgAction::Help)]
     help: Option<bool>,
     #[arg(long, help = "Don't use proxy environment variables")]
@@ -73,6 +225,57 @@ pub struct ReqArgs {
     pub timeout: Option<u64>,
     #[arg(short, long, value_enum, help = "To follow or not follow redirects")]
     pub redirect_mode: Option<RedirectMode>,
+    #[arg(long, help = "Maximum number of redirects to follow. Default 20")]
+    pub max_redirs: Option<u32>,
+    #[arg(
+        long,
+        help = "Keep POST when following a 301 redirect instead of switching to GET"
+    )]
+    pub post301: bool,
+    #[arg(
+        long,
+        help = "Keep POST when following a 302 redirect instead of switching to GET"
+    )]
+    pub post302: bool,
+    #[arg(
+        long,
+        help = "Send credentials to other hosts when following redirects"
+    )]
+    pub location_trusted: bool,
+    #[arg(long, help = "Allow redirects from https to http")]
+    pub redirect_allow_downgrade: bool,
+    #[arg(long, help = "Only follow redirects to the same host")]
+    pub redirect_same_host: bool,
+    #[arg(
+        long,
+        help = "Only follow redirects to this domain and its subdomains, can be repeated",
+        value_delimiter = ','
+    )]
+    pub redirect_allow_domain: Vec<String>,
+    #[arg(
+        long,
+        help = "Retry the request up to this many times on transient failures"
+    )]
+    pub retry: Option<u32>,
+    #[arg(
+        long,
+        help = "Initial delay in seconds between retries, doubled for each retry. Default 1"
+    )]
+    pub retry_delay: Option<u64>,
+    #[arg(long, help = "Maximum time in seconds to spend retrying")]
+    pub retry_max_time: Option<u64>,
+    #[arg(
+        long,
+        help = "Comma separated status codes, 'connect' or 'timeout' to retry on",
+        value_delimiter = ',',
+        value_parser = RetryOn::try_from
+    )]
+    pub retry_on: Option<Vec<RetryOn>>,
+    #[arg(
+        long,
+        help = "Retry requests with non-idempotent methods, such as POST"
+    )]
+    pub retry_all_methods: bool,
 }
 
 #[derive(Subcommand)]
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 
+use super::profile::{HostProfile, is_reference, resolve};
+use super::template::Variable;
 use crate::error;
 use crate::error::Error;
-use crate::{io::read_file, modes::RedirectMode};
+use crate::io::read_file;
+use crate::modes::{RedirectMode, RedirectPolicy};
+use std::collections::HashMap;
 use std::env;
 use std::fs::{DirBuilder, OpenOptions};
 use std::io::Write;
@@ -11,6 +15,40 @@ use std::path::PathBuf;
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub redirect_mode: Option<RedirectMode>,
+    pub max_redirs: Option<u32>,
+    pub redirect_policy: Option<RedirectPolicy>,
+    pub headers: Option<HashMap<String, String>>,
+    pub hosts: Option<HashMap<String, HostProfile>>,
+    pub redact: Option<Vec<String>>,
+    pub environments: Option<HashMap<String, HashMap<String, String>>>,
+}
+
+impl Config {
+    /// The profile for `host`, matched case insensitively.
+    pub fn profile(&self, host: &str) -> Option<&HostProfile> {
+        self.hosts
+            .as_ref()?
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(host))
+            .map(|(_, profile)| profile)
+    }
+
+    /// The variables of a named `[environments.<name>]` table. Values may
+    /// reference secrets like host profiles.
+    pub fn environment(&self, name: &str) -> Result<Vec<Variable>, Error> {
+        let Some(environment) = self.environments.as_ref().and_then(|envs| envs.get(name)) else {
+            error!(&format!("environment {name} is not in the config file"))
+        };
+        let mut variables = Vec::new();
+        for (name, value) in environment {
+            variables.push(Variable {
+                name: name.clone(),
+                value: resolve(value)?,
+                secret: is_reference(value),
+            });
+        }
+        Ok(variables)
+    }
 }
 
 pub fn load_config() -> Result<Option<Config>, Error> {
@@ -27,29 +65,39 @@ pub fn load_config() -> Result<Option<Config>, Error> {
     Ok(Some(config))
 }
 
-fn get_config_path() -> Result<PathBuf, Error> {
+pub fn get_config_dir() -> Result<PathBuf, Error> {
     let mut dir = match env::home_dir() {
         Some(dir) => dir,
         None => error!("Can't get home directory"),
     };
-    dir.push(".config/hur/config.toml");
+    dir.push(".config/hur");
     Ok(dir)
 }
 
+fn get_config_path() -> Result<PathBuf, Error> {
+    Ok(get_config_dir()?.join("config.toml"))
+}
+
 pub fn create_default_config() -> Result<(), Error> {
     let path = get_config_path()?;
     if path.exists() {
         error!("Config file already exists")
     }
 
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            DirBuilder::new().create(parent)?;
-        }
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        DirBuilder::new().create(parent)?;
     }
 
     let config = Config {
         redirect_mode: Some(RedirectMode::NoFollow),
+        max_redirs: None,
+        redirect_policy: None,
+        headers: None,
+        hosts: None,
+        redact: None,
+        environments: None,
     };
     let config_string = match toml::to_string(&config) {
         Ok(string) => string,
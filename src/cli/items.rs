@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+
+use serde_json::{Map, Value};
+
+use crate::error;
+use crate::error::Error;
+use crate::http::headers::Header;
+use crate::io::read_file;
+
+// Longer separators are listed first, so `==` wins over `=` at the same position
+const SEPARATORS: [&str; 6] = [":=@", ":=", "==", "=@", "=", ":"];
+
+/// Headers, query parameters and JSON body fields given as positional items
+/// after the URL, in the style of HTTPie.
+#[derive(Default)]
+pub struct RequestItems {
+    pub headers: Vec<Header>,
+    pub query: Vec<(String, String)>,
+    pub json: Option<Value>,
+}
+
+/// Parses items such as `Header:value`, `param==value`, `field=value`,
+/// `field:=json`, `field=@file` and `field:=@file.json`. Field names can be
+/// nested paths, like `owner[name]=Luffy` or `tags[]=pirate`.
+pub fn parse_items(items: &[String]) -> Result<RequestItems, Error> {
+    let mut parsed = RequestItems::default();
+    for item in items {
+        let Some((key, separator, value)) = split_item(item) else {
+            error!(&format!(
+                "invalid request item \"{item}\", expected Header:value, param==value, field=value or field:=json"
+            ))
+        };
+        match separator {
+            ":" => parsed.headers.push(Header::try_from(item)?),
+            "==" => parsed.query.push((key.to_string(), value.to_string())),
+            _ => {
+                let value = match separator {
+                    "=" => Value::String(value.to_string()),
+                    "=@" => Value::String(read_file(&PathBuf::from(value))?),
+                    ":=" => parse_json(item, value)?,
+                    _ => parse_json(item, &read_file(&PathBuf::from(value))?)?,
+                };
+                let json = parsed.json.get_or_insert(Value::Object(Map::new()));
+                set_path(json, &parse_path(key)?, value)?;
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Whether the item is a `param==value` query parameter.
+pub fn is_query_item(item: &str) -> bool {
+    matches!(split_item(item), Some((_, "==", _)))
+}
+
+fn split_item(item: &str) -> Option<(&str, &str, &str)> {
+    for (index, _) in item.char_indices().filter(|(_, c)| *c == ':' || *c == '=') {
+        if index == 0 {
+            return None;
+        }
+        let rest = &item[index..];
+        if let Some(separator) = SEPARATORS.iter().find(|s| rest.starts_with(*s)) {
+            return Some((&item[..index], separator, &rest[separator.len()..]));
+        }
+    }
+    None
+}
+
+fn parse_json(item: &str, json: &str) -> Result<Value, Error> {
+    match serde_json::from_str(json) {
+        Ok(value) => Ok(value),
+        Err(why) => error!(&format!("invalid JSON in request item \"{item}\": {why}")),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    Append,
+}
+
+// `a[b][0][]` becomes the key a, the key b, index 0 and an append
+fn parse_path(key: &str) -> Result<Vec<PathSegment>, Error> {
+    let (name, mut rest) = match key.find('[') {
+        Some(index) => (&key[..index], &key[index..]),
+        None => (key, ""),
+    };
+    let mut path = vec![PathSegment::Key(name.to_string())];
+    while !rest.is_empty() {
+        let Some(end) = rest.find(']').filter(|_| rest.starts_with('[')) else {
+            error!(&format!("invalid field path \"{key}\""))
+        };
+        let segment = &rest[1..end];
+        path.push(match segment.parse::<usize>() {
+            Ok(index) => PathSegment::Index(index),
+            Err(_) if segment.is_empty() => PathSegment::Append,
+            Err(_) => PathSegment::Key(segment.to_string()),
+        });
+        rest = &rest[end + 1..];
+    }
+    Ok(path)
+}
+
+fn set_path(target: &mut Value, path: &[PathSegment], value: Value) -> Result<(), Error> {
+    let Some((segment, rest)) = path.split_first() else {
+        *target = value;
+        return Ok(());
+    };
+    if target.is_null() {
+        *target = match segment {
+            PathSegment::Key(_) => Value::Object(Map::new()),
+            _ => Value::Array(Vec::new()),
+        };
+    }
+
+    let next = match (segment, target) {
+        (PathSegment::Key(key), Value::Object(map)) => {
+            map.entry(key.clone()).or_insert(Value::Null)
+        }
+        (PathSegment::Index(index), Value::Array(array)) => {
+            if array.len() <= *index {
+                array.resize(index + 1, Value::Null);
+            }
+            &mut array[*index]
+        }
+        (PathSegment::Append, Value::Array(array)) => {
+            array.push(Value::Null);
+            array.last_mut().unwrap()
+        }
+        (segment, target) => error!(&format!(
+            "can't set {segment:?} in JSON value {target}, the field types conflict"
+        )),
+    };
+    set_path(next, rest, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn items(items: &[&str]) -> RequestItems {
+        let items: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+        parse_items(&items).unwrap()
+    }
+
+    #[test]
+    fn test_item_kinds() {
+        let parsed = items(&[
+            "X-Team:core",
+            "Referer:http://petstore.com",
+            "page==2",
+            "name=Luffy",
+            "age:=19",
+            "url=http://petstore.com/a=b",
+        ]);
+        assert_eq!(parsed.headers.len(), 2);
+        assert_eq!(parsed.query, vec![("page".to_string(), "2".to_string())]);
+        assert_eq!(
+            parsed.json.unwrap(),
+            json!({"name": "Luffy", "age": 19, "url": "http://petstore.com/a=b"})
+        );
+    }
+
+    #[test]
+    fn test_nested_paths() {
+        let parsed = items(&[
+            "owner[name]=Luffy",
+            "owner[crew][0]=Zoro",
+            "owner[crew][1]=Nami",
+            "tags[]=pirate",
+            "tags[]=captain",
+            "bounty[amount]:=3000000000",
+        ]);
+        assert_eq!(
+            parsed.json.unwrap(),
+            json!({
+                "owner": {"name": "Luffy", "crew": ["Zoro", "Nami"]},
+                "tags": ["pirate", "captain"],
+                "bounty": {"amount": 3000000000u64}
+            })
+        );
+    }
+
+    #[test]
+    fn test_file_fields() {
+        let path = std::env::temp_dir().join("hur_items_test.json");
+        std::fs::write(&path, r#"{"fruit":"gomu gomu"}"#).unwrap();
+        let parsed = items(&[
+            &format!("raw=@{}", path.display()),
+            &format!("devil:=@{}", path.display()),
+        ]);
+        assert_eq!(
+            parsed.json.unwrap(),
+            json!({"raw": r#"{"fruit":"gomu gomu"}"#, "devil": {"fruit": "gomu gomu"}})
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_invalid_items() {
+        for item in ["novalue", "=value", "a:=not json", "a[b=1"] {
+            assert!(parse_items(&[item.to_string()]).is_err(), "{item}");
+        }
+        let conflicting = ["a=1".to_string(), "a[b]=2".to_string()];
+        assert!(parse_items(&conflicting).is_err());
+    }
+}
@@ -1,20 +1,40 @@
+use crate::auth::netrc::Netrc;
+use crate::auth::oauth2::ClientCredentials;
+use crate::auth::sigv4::{AwsCredentials, SigningConfig, sign};
+use crate::auth::{AuthType, Credentials};
+use crate::error;
 use crate::error::Error;
-use crate::http::request::Request;
+use crate::http::headers::Header;
+use crate::http::redact::{self, Redaction};
+use crate::http::request::{DEFAULT_TIMEOUT, Request};
+use crate::io::write_file;
 use crate::logs::enable_debug;
-use crate::modes::RedirectMode;
-use crate::requester::Requester;
+use crate::modes::{RedirectMode, RedirectPolicy};
 use crate::requester::connector::{Connector, ProxyConnector, RegularConnector};
+use crate::requester::redirect::RedirectOptions;
+use crate::requester::retry::RetryPolicy;
+use crate::requester::{DEFAULT_MAX_REDIRECTS, Requester};
 
 use clap::Parser;
 use command::{Cli, Commands, ConfigCommands, ReqArgs};
-use config::load_config;
+use config::{Config, get_config_dir, load_config};
+use items::{is_query_item, parse_items};
 use output::handle_output;
-use parsing::{parse_body, parse_headers, parse_url};
+use parsing::{parse_body, parse_cookies, parse_headers, parse_url};
+use profile::{HostProfile, merge_headers};
+use session::Session;
+use std::time::{Duration, SystemTime};
+use template::{Variables, parse_env_file};
+use url::Url;
 
 mod command;
 mod config;
+mod items;
 mod output;
 mod parsing;
+mod profile;
+mod session;
+mod template;
 
 struct DefaultModes {
     redirect_mode: RedirectMode,
@@ -31,7 +51,7 @@ pub fn handle_args(args: Vec<String>) -> Result<(), Error> {
     }
 
     match parser.command {
-        Some(Commands::Req(req_args)) => handle_req(req_args),
+        Some(Commands::Req(req_args)) => handle_req(*req_args),
         Some(Commands::Config { command }) => handle_config(command),
         None => unreachable!(),
     }
@@ -39,48 +59,325 @@ pub fn handle_args(args: Vec<String>) -> Result<(), Error> {
 
 fn handle_req(req: ReqArgs) -> Result<(), Error> {
     let config = load_config()?;
-    let parsed_url = parse_url(&req.url)?;
-    let body = parse_body(req.body, req.body_json, req.body_file)?;
+    let (req, secrets) = render_variables(req, config.as_ref())?;
+    let mut redacted_headers = config
+        .as_ref()
+        .and_then(|conf| conf.redact.clone())
+        .unwrap_or_default();
+    redacted_headers.extend(req.redact.iter().cloned());
+    redact::configure(Redaction::new(&redacted_headers, !req.no_redact).with_secrets(&secrets));
+    let retry_policy = retry_policy(&req);
+    let items = parse_items(&req.items)?;
+    let mut parsed_url = parse_url(&req.url)?;
+    // Only added if there are parameters, as an empty query leaves a trailing ?
+    if !req.query.is_empty() || !items.query.is_empty() {
+        parsed_url
+            .query_pairs_mut()
+            .extend_pairs(req.query.iter().chain(&items.query));
+    }
+    let profile = config
+        .as_ref()
+        .zip(parsed_url.host_str())
+        .and_then(|(conf, host)| conf.profile(host));
+    let mut session = match &req.session {
+        Some(name) => Some(Session::load(name)?),
+        None => None,
+    };
+    let auth = auth(&req, &parsed_url, profile, session.as_ref())?;
+    let signing = match &req.aws_sigv4 {
+        Some(value) => Some((SigningConfig::try_from(value)?, AwsCredentials::from_env()?)),
+        None => None,
+    };
+    let mut cookie_jar = parse_cookies(req.cookie, &parsed_url)?;
+    let mut body = parse_body(
+        req.body,
+        req.body_json,
+        req.body_file,
+        req.form_urlencoded,
+        req.form,
+        items.json,
+    )?;
+    if let Some(input_body) = &mut body {
+        match req.chunked {
+            true => input_body.content.set_chunked(),
+            false => input_body.content.buffer()?,
+        }
+        if let Some(compression) = req.compress_body {
+            input_body.content.compress(compression)?;
+        }
+    }
 
     // gör snyggare?
     let redirect_mode = if let Some(mode) = req.redirect_mode {
         mode
-    } else if let Some(conf) = config {
-        conf.redirect_mode.unwrap_or(DEFAULT_MODES.redirect_mode)
+    } else if let Some(conf) = &config {
+        conf.redirect_mode
+            .clone()
+            .unwrap_or(DEFAULT_MODES.redirect_mode)
     } else {
         DEFAULT_MODES.redirect_mode
     };
+    let max_redirects = req
+        .max_redirs
+        .or(config.as_ref().and_then(|conf| conf.max_redirs))
+        .unwrap_or(DEFAULT_MAX_REDIRECTS);
+    let redirect_policy = config
+        .as_ref()
+        .and_then(|conf| conf.redirect_policy.clone())
+        .unwrap_or_default()
+        .merge(RedirectPolicy {
+            allow_downgrade: req.redirect_allow_downgrade,
+            same_host_only: req.redirect_same_host,
+            allowed_domains: req.redirect_allow_domain.clone(),
+        });
 
-    let mut headers = parse_headers(req.header, req.headers_json)?;
+    let header_args = req.header.into_iter().flatten().chain(items.headers);
+    let mut headers = parse_headers(Some(header_args.collect()), req.headers_json)?;
+    if let Some(user_agent) = &req.user_agent
+        && headers.get("User-Agent").is_none()
+    {
+        headers.add("User-Agent", user_agent);
+    }
+    let mut digest_credentials = None;
+    match auth {
+        Some(Auth::Header(authorization)) if headers.get("Authorization").is_none() => {
+            headers.add("Authorization", &authorization)
+        }
+        Some(Auth::Digest(credentials)) => digest_credentials = Some(credentials),
+        _ => {}
+    }
+    if let Some(session) = &mut session {
+        session.merge_headers(&mut headers, &parsed_url);
+        session.merge_cookies(&mut cookie_jar);
+    }
+    // Added after the session, so that resolved secrets are not stored in it
+    if let Some(profile) = profile {
+        profile.merge_headers(&mut headers)?;
+        if headers.get("Authorization").is_none()
+            && req.aws_sigv4.is_none()
+            && let Some(authorization) = profile.authorization()?
+        {
+            headers.add_secret("Authorization", &authorization);
+        }
+    }
+    if let Some(configured) = config.as_ref().and_then(|conf| conf.headers.as_ref()) {
+        merge_headers(configured, &mut headers)?;
+    }
     if let Some(input_body) = &body
         && let Some(content_type) = &input_body.content_type
+        && headers.get("Content-Type").is_none()
     {
         headers.add("Content-Type", content_type);
     }
+    if let Some(compression) = req.compress_body
+        && body.is_some()
+    {
+        headers.remove("Content-Encoding");
+        headers.add("Content-Encoding", compression.content_encoding());
+    }
+    if req.expect_continue && body.is_some() && headers.get("Expect").is_none() {
+        headers.add("Expect", "100-continue");
+    }
 
-    let request = match body {
-        Some(body) => Request::with_body(
-            parsed_url,
-            req.method,
-            headers,
-            body.content.as_str(),
-            req.timeout,
-        )?,
+    let mut request = match body {
+        Some(body) => {
+            Request::with_body(parsed_url, req.method, headers, body.content, req.timeout)?
+        }
         None => Request::new(parsed_url, req.method, headers, req.timeout)?,
     };
+    if let Some(seconds) = req.expect_timeout {
+        request.expect_timeout = expect_timeout(seconds)?;
+    }
+    if let Some((config, credentials)) = &signing {
+        sign(&mut request, config, credentials, SystemTime::now())?;
+    }
 
-    // "no_proxy" is actually proxy??
-    let connector: Box<dyn Connector> = if req.no_proxy {
-        Box::new(ProxyConnector::new(request.timeout))
-    } else {
-        Box::new(RegularConnector::new(request.timeout))
-    };
-    let requester = Requester::new(connector, redirect_mode);
+    let mut requester = Requester::new(connector(req.no_proxy, request.timeout), redirect_mode)
+        .with_retry(retry_policy)
+        .with_max_redirects(max_redirects)
+        .with_redirect_options(RedirectOptions {
+            post301: req.post301,
+            post302: req.post302,
+            location_trusted: req.location_trusted,
+            policy: redirect_policy,
+        })
+        .with_cookie_jar(cookie_jar);
+    if let Some(credentials) = digest_credentials {
+        requester = requester.with_digest_auth(credentials);
+    }
     let request_output = serde_json::to_value(&request)?;
     let response = requester.do_request(request)?;
+    if let Some(path) = &req.cookie_jar {
+        write_file(path, requester.cookie_jar().to_netscape().as_bytes())?;
+    }
+    if let (Some(session), Some(name)) = (&mut session, &req.session) {
+        session.update_cookies(requester.cookie_jar());
+        session.save(name)?;
+    }
     handle_output(response, request_output, req.verbose)
 }
 
+// Replaces {{name}} placeholders in the URL, headers and body, but not in the
+// content of files, which are streamed when the request is sent. Config
+// environments come first, then the env file and then --var.
+// Placeholders in the URL and query are always rendered, so that undefined
+// variables are an error. The rest is only rendered when variables are given,
+// so bodies such as Handlebars templates are sent as they are otherwise.
+// Returns the values of secret variables, which have to be redacted.
+fn render_variables(
+    mut req: ReqArgs,
+    config: Option<&Config>,
+) -> Result<(ReqArgs, Vec<String>), Error> {
+    let given = req.env.is_some() || req.env_file.is_some() || !req.var.is_empty();
+    let mut variables = Variables::default();
+    if let Some(name) = &req.env {
+        match config {
+            Some(config) => variables.extend(config.environment(name)?),
+            None => error!(&format!(
+                "environment {name} given, but there is no config file"
+            )),
+        }
+    }
+    if let Some(path) = &req.env_file {
+        variables.extend(parse_env_file(path)?);
+    }
+    variables.extend(req.var.drain(..));
+
+    req.url = variables.render(&req.url)?;
+    for (key, value) in &mut req.query {
+        *key = variables.render(key)?;
+        *value = variables.render(value)?;
+    }
+    for item in &mut req.items {
+        if given || is_query_item(item) {
+            *item = variables.render(item)?;
+        }
+    }
+    if !given {
+        return Ok((req, Vec::new()));
+    }
+    if let Some(headers) = &mut req.header {
+        for header in headers.iter_mut() {
+            *header = Header::try_from(&format!(
+                "{}:{}",
+                variables.render(header.key())?,
+                variables.render(header.value())?
+            ))?;
+        }
+    }
+    if let Some(fields) = &mut req.form_urlencoded {
+        for field in fields.iter_mut() {
+            *field = field.render(&variables)?;
+        }
+    }
+    if let Some(fields) = &mut req.form {
+        for field in fields.iter_mut() {
+            *field = field.render(|text| variables.render(text))?;
+        }
+    }
+    for value in [
+        &mut req.headers_json,
+        &mut req.body,
+        &mut req.body_json,
+        &mut req.bearer,
+        &mut req.user,
+        &mut req.user_agent,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        *value = variables.render(value)?;
+    }
+    Ok((req, variables.secrets()))
+}
+
+fn expect_timeout(seconds: f64) -> Result<Duration, Error> {
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(timeout) if !timeout.is_zero() => Ok(timeout),
+        _ => error!(&format!(
+            "invalid expect timeout {seconds}, expected a number of seconds greater than 0"
+        )),
+    }
+}
+
+fn connector(no_proxy: bool, timeout: u64) -> Box<dyn Connector> {
+    // "no_proxy" is actually proxy??
+    if no_proxy {
+        Box::new(ProxyConnector::new(timeout))
+    } else {
+        Box::new(RegularConnector::new(timeout))
+    }
+}
+
+enum Auth {
+    Header(String),
+    Digest(Credentials),
+}
+
+fn auth(
+    req: &ReqArgs,
+    url: &Url,
+    profile: Option<&HostProfile>,
+    session: Option<&Session>,
+) -> Result<Option<Auth>, Error> {
+    if let Some(token) = &req.bearer {
+        return Ok(Some(Auth::Header(format!("Bearer {token}"))));
+    }
+
+    if let Some(token_url) = &req.oauth2_token_url {
+        let client = ClientCredentials {
+            token_url: parse_url(token_url)?,
+            client_id: req.client_id.clone().unwrap_or_default(),
+            client_secret: req.client_secret.clone().unwrap_or_default(),
+            scope: req.scope.clone(),
+        };
+        let timeout = req.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let mut requester =
+            Requester::new(connector(req.no_proxy, timeout), RedirectMode::NoFollow);
+        let cache_path = get_config_dir()?.join("oauth2_tokens.json");
+        let token = client.access_token(&mut requester, &cache_path, req.timeout)?;
+        return Ok(Some(Auth::Header(format!("Bearer {token}"))));
+    }
+
+    if req.aws_sigv4.is_some() {
+        return Ok(None);
+    }
+
+    let credentials = match &req.user {
+        Some(user) => Some(Credentials::parse(user)?),
+        // Credentials in the host profile are preferred over netrc
+        None if profile.is_some_and(|p| p.user.is_some() || p.bearer.is_some()) => None,
+        // And so is auth stored in the session
+        None if session.is_some_and(|s| s.authorization(url).is_some()) => None,
+        None => {
+            let netrc = match &req.netrc_file {
+                Some(path) => Netrc::load(path, req.netrc_optional)?,
+                None => Netrc::load_default()?,
+            };
+            url.host_str().and_then(|host| netrc.credentials(host))
+        }
+    };
+    Ok(credentials.map(
+        |credentials| match req.auth_type.clone().unwrap_or(AuthType::Basic) {
+            AuthType::Basic => Auth::Header(credentials.basic()),
+            AuthType::Digest => Auth::Digest(credentials),
+        },
+    ))
+}
+
+fn retry_policy(req: &ReqArgs) -> RetryPolicy {
+    let mut policy = RetryPolicy::new(req.retry.unwrap_or(0));
+    if let Some(delay) = req.retry_delay {
+        policy.delay = Duration::from_secs(delay);
+    }
+    policy.max_time = req.retry_max_time.map(Duration::from_secs);
+    if let Some(retry_on) = &req.retry_on {
+        policy.retry_on = retry_on.clone();
+    }
+    policy.all_methods = req.retry_all_methods;
+    policy
+}
+
 fn handle_config(command: Option<ConfigCommands>) -> Result<(), Error> {
     match command {
         Some(ConfigCommands::Create) => config::create_default_config(),
@@ -98,10 +395,35 @@ fn test_parse_body() {
     } else {
         panic!()
     };
-    let body = parse_body(args.body, args.body_json, args.body_file).unwrap();
+    let body = parse_body(
+        args.body,
+        args.body_json,
+        args.body_file,
+        args.form_urlencoded,
+        args.form,
+        None,
+    )
+    .unwrap();
 
     assert!(body.is_some());
-    assert_eq!(body.unwrap().content, "form:value");
+    assert_eq!(body.unwrap().content.as_text().unwrap(), "form:value");
+}
+
+#[test]
+fn test_parse_body_starting_with_at() {
+    let body = parse_body(
+        Some("@channel deploy done".to_string()),
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(body.content.as_text().unwrap(), "@channel deploy done");
+    assert!(body.content_type.is_none());
 }
 
 #[test]
@@ -120,11 +442,18 @@ fn test_parse_json_body() {
         panic!()
     };
 
-    let body = parse_body(args.body, args.body_json, args.body_file)
-        .unwrap()
-        .unwrap();
+    let body = parse_body(
+        args.body,
+        args.body_json,
+        args.body_file,
+        args.form_urlencoded,
+        args.form,
+        None,
+    )
+    .unwrap()
+    .unwrap();
 
-    assert_eq!(body.content, r#"{"key":"value"}"#);
+    assert_eq!(body.content.as_text().unwrap(), r#"{"key":"value"}"#);
     assert_eq!(body.content_type.unwrap(), "application/json");
 }
 
@@ -149,10 +478,69 @@ fn test_parse_body_file() {
     use std::io::Write;
     file.write_all(br#"{"key":"value"}"#).unwrap();
 
-    let body = parse_body(args.body, args.body_json, args.body_file)
-        .unwrap()
-        .unwrap();
+    let body = parse_body(
+        args.body,
+        args.body_json,
+        args.body_file,
+        args.form_urlencoded,
+        args.form,
+        None,
+    )
+    .unwrap()
+    .unwrap();
+    let mut content = Vec::new();
+    body.content.write_to(&mut content).unwrap();
 
-    assert_eq!(body.content, r#"{"key":"value"}"#);
+    assert_eq!(content, br#"{"key":"value"}"#);
     assert_eq!(body.content_type.unwrap(), "application/json");
 }
+
+#[test]
+fn test_expect_timeout() {
+    assert_eq!(expect_timeout(0.5).unwrap(), Duration::from_millis(500));
+    for seconds in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        let err = expect_timeout(seconds).err().unwrap();
+        assert!(err.message.contains("greater than 0"), "{}", err.message);
+    }
+}
+
+#[test]
+fn test_render_variables() {
+    let parse = |input: &[&str]| match Cli::parse_from(input).command.unwrap() {
+        Commands::Req(req_args) => render_variables(*req_args, None),
+        _ => panic!(),
+    };
+
+    let (args, _) = parse(&["hur", "req", "http://localhost", "--body", "Hello {{name}}"]).unwrap();
+    assert_eq!(args.body.unwrap(), "Hello {{name}}");
+
+    for input in [
+        vec!["hur", "req", "http://localhost/{{id}}"],
+        vec!["hur", "req", "http://localhost", "id=={{id}}"],
+        vec!["hur", "req", "http://localhost", "-q", "id={{id}}"],
+    ] {
+        let err = parse(&input).err().unwrap();
+        assert!(
+            err.message.contains("undefined variable id"),
+            "{}",
+            err.message
+        );
+    }
+
+    let err = parse(&[
+        "hur",
+        "req",
+        "http://localhost/{{id}}",
+        "--body",
+        "Hello {{name}}",
+        "--var",
+        "id=42",
+    ])
+    .err()
+    .unwrap();
+    assert!(
+        err.message.contains("undefined variable name"),
+        "{}",
+        err.message
+    );
+}
@@ -1,4 +1,5 @@
 use crate::error::Error;
+use crate::http::redact::redaction;
 use crate::http::response::Response;
 
 pub fn handle_output(
@@ -8,7 +9,8 @@ pub fn handle_output(
 ) -> Result<(), Error> {
     if verbose {
         let json_output = serde_json::json!({"request": request, "response":response});
-        println!("{}", serde_json::to_string_pretty(&json_output)?);
+        let output = serde_json::to_string_pretty(&json_output)?;
+        println!("{}", redaction().redact_text(&output));
     } else if let Some(body) = &response.body {
         println!("{body}")
     }
@@ -1,10 +1,15 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use url::Url;
 
+use super::template::Variables;
 use crate::error;
 use crate::error::Error;
+use crate::http::body::Body;
+use crate::http::cookies::{Cookie, CookieJar};
 use crate::http::headers::{Header, Headers};
+use crate::http::mime;
+use crate::http::multipart::{Multipart, MultipartField};
 use crate::io::read_file;
 
 pub fn parse_url(url: &str) -> Result<Url, Error> {
@@ -19,18 +24,78 @@ pub fn parse_url(url: &str) -> Result<Url, Error> {
 }
 
 pub struct InputBody {
-    pub content: String,
+    pub content: Body,
     pub content_type: Option<String>,
 }
 
+#[derive(Clone, Debug)]
+pub struct FormField {
+    key: String,
+    value: String,
+}
+
+impl FormField {
+    pub fn try_from(field: &str) -> Result<Self, Error> {
+        match field.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok(FormField {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            _ => error!(&format!(
+                "invalid form field \"{field}\", expected key=value"
+            )),
+        }
+    }
+
+    pub fn render(&self, variables: &Variables) -> Result<Self, Error> {
+        Ok(FormField {
+            key: variables.render(&self.key)?,
+            value: variables.render(&self.value)?,
+        })
+    }
+}
+
+/// A query parameter given as `key=value`.
+pub fn parse_query_param(param: &str) -> Result<(String, String), Error> {
+    match param.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => error!(&format!(
+            "invalid query parameter \"{param}\", expected key=value"
+        )),
+    }
+}
+
 pub fn parse_body(
     input_body: Option<String>,
     input_json: Option<String>,
     input_body_file: Option<PathBuf>,
+    input_form: Option<Vec<FormField>>,
+    input_multipart: Option<Vec<MultipartField>>,
+    input_items_json: Option<serde_json::Value>,
 ) -> Result<Option<InputBody>, Error> {
+    if let Some(json) = input_items_json {
+        if input_body.is_some()
+            || input_json.is_some()
+            || input_body_file.is_some()
+            || input_form.is_some()
+            || input_multipart.is_some()
+        {
+            error!("request item fields can't be combined with another request body")
+        }
+        return Ok(Some(InputBody {
+            content: Body::from(json.to_string()),
+            content_type: Some("application/json".to_string()),
+        }));
+    }
     if let Some(body) = input_body {
+        // `@-` reads the body from stdin like curl, other values starting with @
+        // are sent as they are since files are read with --body-file
+        let content = match body.as_str() {
+            "@-" => Body::stream(std::io::stdin()),
+            _ => Body::from(body),
+        };
         return Ok(Some(InputBody {
-            content: body.to_string(),
+            content,
             content_type: None,
         }));
     }
@@ -38,7 +103,7 @@ pub fn parse_body(
         match serde_json::from_str::<serde_json::Value>(&body) {
             Ok(_) => {
                 return Ok(Some(InputBody {
-                    content: body.to_string(),
+                    content: Body::from(body),
                     content_type: Some("application/json".to_string()),
                 }));
             }
@@ -46,20 +111,50 @@ pub fn parse_body(
         };
     }
     if let Some(path) = input_body_file {
-        let file_str = read_file(&path)?;
-        let content_type = match path.extension() {
-            Some(extension) if extension == "json" => Some("application/json".to_string()),
-            _ => None,
-        };
+        if path.as_os_str() == "-" {
+            return Ok(Some(InputBody {
+                content: Body::stream(std::io::stdin()),
+                content_type: None,
+            }));
+        }
         return Ok(Some(InputBody {
-            content: file_str,
-            content_type,
+            content: file_body(path.clone())?,
+            content_type: file_type(&path)?,
+        }));
+    }
+    if let Some(fields) = input_form {
+        let mut form = url::form_urlencoded::Serializer::new(String::new());
+        for field in &fields {
+            form.append_pair(&field.key, &field.value);
+        }
+        return Ok(Some(InputBody {
+            content: Body::from(form.finish()),
+            content_type: Some("application/x-www-form-urlencoded".to_string()),
+        }));
+    }
+    if let Some(fields) = input_multipart {
+        let multipart = Multipart::new(fields);
+        return Ok(Some(InputBody {
+            content: multipart.body(),
+            content_type: Some(multipart.content_type()),
         }));
     }
 
     Ok(None)
 }
 
+// Files are streamed when the request is sent, so binary and large files work
+fn file_body(path: PathBuf) -> Result<Body, Error> {
+    if !path.is_file() {
+        error!(&format!("body file {} does not exist", path.display()))
+    }
+    Ok(Body::file(path))
+}
+
+fn file_type(path: &Path) -> Result<Option<String>, Error> {
+    Ok(mime::detect(path)?.map(String::from))
+}
+
 pub fn parse_headers(
     input_header: Option<Vec<Header>>,
     input_headers: Option<String>,
@@ -86,3 +181,75 @@ fn json_headers(headers_json: &str) -> Result<Headers, Error> {
     let map: std::collections::HashMap<String, String> = serde_json::from_str(&json_string)?;
     Ok(Headers::from(map))
 }
+
+/// Loads cookies from a Netscape cookie file, or from `name=value` pairs which are
+/// sent to the host of `url`.
+pub fn parse_cookies(input_cookie: Option<String>, url: &Url) -> Result<CookieJar, Error> {
+    let cookie = match input_cookie {
+        Some(cookie) => cookie,
+        None => return Ok(CookieJar::new()),
+    };
+
+    let path = PathBuf::from(&cookie);
+    if path.is_file() {
+        return CookieJar::from_netscape(&read_file(&path)?);
+    }
+    if !cookie.contains('=') {
+        error!(&format!("cookie file \"{cookie}\" does not exist"))
+    }
+
+    let mut jar = CookieJar::new();
+    for pair in cookie.split(';').filter(|pair| !pair.trim().is_empty()) {
+        match Cookie::parse(&format!("{}; Path=/", pair.trim()), url) {
+            Some(cookie) => jar.add(cookie),
+            None => error!(&format!("invalid cookie \"{pair}\"")),
+        }
+    }
+    Ok(jar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cookie_pairs() {
+        let url = Url::parse("http://example.com/a/b").unwrap();
+        let jar = parse_cookies(Some("a=1; b=2".to_string()), &url).unwrap();
+        assert_eq!(jar.header(&url), Some("a=1; b=2".to_string()));
+        assert_eq!(jar.header(&Url::parse("http://other.com/").unwrap()), None);
+    }
+
+    #[test]
+    fn test_parse_form_body() {
+        let fields = vec![
+            FormField::try_from("name=Monkey D. Luffy").unwrap(),
+            FormField::try_from("crew=straw&hat").unwrap(),
+            FormField::try_from("empty=").unwrap(),
+        ];
+        let body = parse_body(None, None, None, Some(fields), None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            body.content.as_text().unwrap(),
+            "name=Monkey+D.+Luffy&crew=straw%26hat&empty="
+        );
+        assert_eq!(
+            body.content_type.unwrap(),
+            "application/x-www-form-urlencoded"
+        );
+    }
+
+    #[test]
+    fn test_invalid_form_field() {
+        assert!(FormField::try_from("novalue").is_err());
+        assert!(FormField::try_from("=value").is_err());
+        assert_eq!(FormField::try_from("a=b=c").unwrap().value, "b=c");
+    }
+
+    #[test]
+    fn test_parse_missing_cookie_file() {
+        let url = Url::parse("http://example.com").unwrap();
+        assert!(parse_cookies(Some("/no/such/cookies.txt".to_string()), &url).is_err());
+    }
+}
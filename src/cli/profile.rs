@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Credentials;
+use crate::error;
+use crate::error::Error;
+use crate::http::headers::Headers;
+use crate::io::read_file;
+
+/// Headers and credentials used for requests to a host, from a `[hosts."<host>"]`
+/// config table. Values may reference secrets with `env:VAR`, `file:/path` or
+/// `cmd:command`, which are resolved when a request is made to the host.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct HostProfile {
+    pub headers: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bearer: Option<String>,
+}
+
+impl HostProfile {
+    /// The Authorization header value for the profile, if it has credentials.
+    pub fn authorization(&self) -> Result<Option<String>, Error> {
+        if let Some(token) = &self.bearer {
+            return Ok(Some(format!("Bearer {}", resolve(token)?)));
+        }
+        match &self.user {
+            Some(user) => {
+                let credentials = Credentials {
+                    user: resolve(user)?,
+                    password: match &self.password {
+                        Some(password) => resolve(password)?,
+                        None => String::new(),
+                    },
+                };
+                Ok(Some(credentials.basic()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Adds the profile headers that are not given for this request. Headers
+    /// referencing secrets are redacted from output.
+    pub fn merge_headers(&self, headers: &mut Headers) -> Result<(), Error> {
+        merge_headers(&self.headers, headers)
+    }
+}
+
+/// Adds the configured headers that are not given for this request, such as
+/// the ones in the `[headers]` config table. Headers referencing secrets are
+/// redacted from output.
+pub fn merge_headers(
+    configured: &HashMap<String, String>,
+    headers: &mut Headers,
+) -> Result<(), Error> {
+    for (key, value) in configured {
+        if headers.get(key).is_some() {
+            continue;
+        }
+        match is_reference(value) {
+            true => headers.add_secret(key, &resolve(value)?),
+            false => headers.add(key, value),
+        }
+    }
+    Ok(())
+}
+
+/// Whether the value is an `env:`, `file:` or `cmd:` secret reference.
+pub fn is_reference(value: &str) -> bool {
+    ["env:", "file:", "cmd:"]
+        .iter()
+        .any(|prefix| value.starts_with(prefix))
+}
+
+/// Resolves a secret reference, returning other values as they are.
+pub fn resolve(value: &str) -> Result<String, Error> {
+    if let Some(var) = value.strip_prefix("env:") {
+        return match std::env::var(var) {
+            Ok(secret) => Ok(secret),
+            Err(_) => error!(&format!("environment variable {var} is not set")),
+        };
+    }
+    if let Some(path) = value.strip_prefix("file:") {
+        let path = match (path.strip_prefix("~/"), std::env::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(path),
+        };
+        return match read_file(&path) {
+            Ok(secret) => Ok(trim_newline(secret)),
+            Err(why) => error!(&format!(
+                "can't read secret file {}: {}",
+                path.display(),
+                why.message
+            )),
+        };
+    }
+    if let Some(command) = value.strip_prefix("cmd:") {
+        return run_command(command);
+    }
+    Ok(value.to_string())
+}
+
+fn run_command(command: &str) -> Result<String, Error> {
+    log::debug!("Running secret command {}", command);
+    #[cfg(not(windows))]
+    let output = Command::new("sh").arg("-c").arg(command).output();
+    #[cfg(windows)]
+    let output = Command::new("cmd").arg("/C").arg(command).output();
+
+    match output {
+        Ok(output) if output.status.success() => Ok(trim_newline(
+            String::from_utf8_lossy(&output.stdout).to_string(),
+        )),
+        Ok(output) => error!(&format!(
+            "secret command \"{command}\" failed with {}",
+            output.status
+        )),
+        Err(why) => error!(&format!("can't run secret command \"{command}\": {why}")),
+    }
+}
+
+// Only the first line is used, like `pass show` which puts the password there
+fn trim_newline(secret: String) -> String {
+    secret.lines().next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Method;
+    use crate::http::request::Request;
+    use crate::requester::redirect::{RedirectOptions, redirect_request};
+    use url::Url;
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve("literal").unwrap(), "literal");
+        assert_eq!(resolve("env:PATH").unwrap(), std::env::var("PATH").unwrap());
+        assert!(resolve("env:HUR_UNSET_VARIABLE").is_err());
+        assert!(resolve("file:/nonexistent/secret").is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_resolve_command() {
+        assert_eq!(resolve("cmd:printf 's3cret\\nmeta'").unwrap(), "s3cret");
+        assert!(resolve("cmd:exit 1").is_err());
+    }
+
+    #[test]
+    fn test_profile_secrets_are_not_redirected() {
+        let profile = HostProfile {
+            headers: HashMap::from([
+                ("X-Api-Key".to_string(), "cmd:echo s3cret".to_string()),
+                ("X-Team".to_string(), "core".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let mut headers = Headers::new();
+        profile.merge_headers(&mut headers).unwrap();
+        let url = Url::parse("http://127.0.0.1:8080/a").unwrap();
+        let request = Request::new(url, Method::Get, headers, None).unwrap();
+
+        let location = Url::parse("http://127.0.0.1:9090/b").unwrap();
+        let redirected =
+            redirect_request(request, location, 302, &RedirectOptions::default()).unwrap();
+        assert!(redirected.headers.get("X-Api-Key").is_none());
+        assert_eq!(
+            redirected.headers.get_first("X-Team"),
+            Some("core".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge_headers() {
+        let profile = HostProfile {
+            headers: HashMap::from([
+                ("X-Api-Key".to_string(), "cmd:echo s3cret".to_string()),
+                ("X-Team".to_string(), "core".to_string()),
+                ("Accept".to_string(), "text/plain".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let mut headers = Headers::new();
+        headers.add("Accept", "application/json");
+        profile.merge_headers(&mut headers).unwrap();
+
+        assert_eq!(headers.get_first("x-api-key"), Some("s3cret".to_string()));
+        assert!(headers.is_secret("x-api-key"));
+        assert!(!headers.is_secret("x-team"));
+        assert_eq!(
+            headers.get("accept"),
+            Some(&vec!["application/json".to_string()])
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::config::get_config_dir;
+use crate::error;
+use crate::error::Error;
+use crate::http::cookies::{Cookie, CookieJar};
+use crate::http::headers::Headers;
+use crate::io::{read_file, write_private_file};
+
+// Headers that describe a single request and should not be reused by a session
+const IGNORED_HEADERS: [&str; 6] = [
+    "host",
+    "connection",
+    "cookie",
+    "authorization",
+    "content-length",
+    "transfer-encoding",
+];
+
+/// Headers, cookies and auth stored between invocations of `hur req --session <name>`.
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Session {
+    /// The origin the session is used with, its headers and auth are only sent there.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+    pub headers: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<String>,
+    pub cookies: Vec<Cookie>,
+}
+
+impl Session {
+    pub fn load(name: &str) -> Result<Session, Error> {
+        let path = session_path(name)?;
+        if !path.exists() {
+            log::debug!("Creating new session {}", name);
+            return Ok(Session::default());
+        }
+        match serde_json::from_str(&read_file(&path)?) {
+            Ok(session) => Ok(session),
+            Err(why) => error!(&format!("invalid session file {}: {why}", path.display())),
+        }
+    }
+
+    pub fn save(&self, name: &str) -> Result<(), Error> {
+        let path = session_path(name)?;
+        // The session holds credentials and cookies
+        write_private_file(&path, serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Adds the session headers and auth to `headers`, unless given for this request.
+    /// Headers given for this request are stored in the session. Requests to
+    /// another origin than the one the session was created for are left alone.
+    pub fn merge_headers(&mut self, headers: &mut Headers, url: &Url) {
+        let origin = url.origin().ascii_serialization();
+        match &self.origin {
+            Some(session_origin) if *session_origin != origin => {
+                log::debug!("Session is for {session_origin}, not adding its headers for {origin}");
+                return;
+            }
+            Some(_) => {}
+            None => self.origin = Some(origin),
+        }
+
+        for (key, values) in headers.iter() {
+            let key = key.to_lowercase();
+            if IGNORED_HEADERS.contains(&key.as_str())
+                || headers.is_secret(&key)
+                || key.starts_with("content-")
+                || key.starts_with("if-")
+            {
+                continue;
+            }
+            if let Some(value) = values.last() {
+                self.headers.insert(key, value.to_string());
+            }
+        }
+        if let Some(auth) = headers.get_first("Authorization") {
+            self.auth = Some(auth);
+        }
+
+        for (key, value) in &self.headers {
+            if headers.get(key).is_none() {
+                headers.add(key, value);
+            }
+        }
+        if let Some(auth) = &self.auth
+            && headers.get("Authorization").is_none()
+        {
+            headers.add("Authorization", auth);
+        }
+    }
+
+    /// The stored auth, if the session is used with the origin of `url`.
+    pub fn authorization(&self, url: &Url) -> Option<&str> {
+        match &self.origin {
+            Some(origin) if *origin == url.origin().ascii_serialization() => self.auth.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn merge_cookies(&self, cookie_jar: &mut CookieJar) {
+        for cookie in &self.cookies {
+            cookie_jar.add(cookie.clone());
+        }
+    }
+
+    pub fn update_cookies(&mut self, cookie_jar: &CookieJar) {
+        self.cookies = cookie_jar.cookies().into_iter().cloned().collect();
+    }
+}
+
+fn session_path(name: &str) -> Result<PathBuf, Error> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !name.starts_with('.');
+    if !valid {
+        error!(&format!(
+            "invalid session name \"{name}\", use letters, digits, '-', '_' and '.'"
+        ))
+    }
+    Ok(get_config_dir()?
+        .join("sessions")
+        .join(format!("{name}.json")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url() -> Url {
+        Url::parse("https://petstore.com/animals").unwrap()
+    }
+
+    #[test]
+    fn test_merge_headers() {
+        let mut session = Session::default();
+        session
+            .headers
+            .insert("accept".to_string(), "text/plain".to_string());
+        session
+            .headers
+            .insert("x-team".to_string(), "core".to_string());
+        session.auth = Some("Bearer old".to_string());
+
+        let mut headers = Headers::new();
+        headers.add("Accept", "application/json");
+        headers.add("Content-Type", "application/json");
+        headers.add("Authorization", "Bearer new");
+        session.merge_headers(&mut headers, &url());
+
+        assert_eq!(
+            headers.get("Accept"),
+            Some(&vec!["application/json".to_string()])
+        );
+        assert_eq!(headers.get_first("X-Team"), Some("core".to_string()));
+        assert_eq!(
+            headers.get("Authorization"),
+            Some(&vec!["Bearer new".to_string()])
+        );
+        assert_eq!(session.auth, Some("Bearer new".to_string()));
+        assert_eq!(session.headers.get("content-type"), None);
+        assert_eq!(
+            session.headers.get("accept"),
+            Some(&"application/json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_session_auth_is_added() {
+        let mut session = Session {
+            auth: Some("Basic Zm9vOmJhcg==".to_string()),
+            ..Default::default()
+        };
+        let mut headers = Headers::new();
+        session.merge_headers(&mut headers, &url());
+        assert_eq!(
+            headers.get_first("Authorization"),
+            Some("Basic Zm9vOmJhcg==".to_string())
+        );
+    }
+
+    #[test]
+    fn test_session_is_bound_to_origin() {
+        let mut session = Session {
+            origin: Some("https://petstore.com".to_string()),
+            auth: Some("Bearer s3cret".to_string()),
+            headers: HashMap::from([("x-team".to_string(), "core".to_string())]),
+            ..Default::default()
+        };
+        let mut headers = Headers::new();
+        headers.add("X-Other", "value");
+        session.merge_headers(&mut headers, &Url::parse("https://evil.com/steal").unwrap());
+
+        assert!(headers.get("Authorization").is_none());
+        assert!(headers.get("X-Team").is_none());
+        assert!(!session.headers.contains_key("x-other"));
+        assert!(
+            session
+                .authorization(&Url::parse("https://evil.com/steal").unwrap())
+                .is_none()
+        );
+        assert_eq!(session.authorization(&url()), Some("Bearer s3cret"));
+    }
+
+    #[test]
+    fn test_invalid_session_name() {
+        assert!(session_path("../secrets").is_err());
+        assert!(session_path("").is_err());
+        assert!(session_path("api-prod_2").is_ok());
+    }
+}
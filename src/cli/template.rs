@@ -0,0 +1,220 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use crate::error;
+use crate::error::Error;
+use crate::io::read_file;
+
+/// A template variable given as `name=value`.
+#[derive(Clone, Debug)]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    /// Whether the value was resolved from a secret reference.
+    pub secret: bool,
+}
+
+impl Variable {
+    pub fn try_from(variable: &str) -> Result<Self, Error> {
+        match variable.split_once('=') {
+            Some((name, value)) if is_name(name.trim()) => Ok(Variable {
+                name: name.trim().to_string(),
+                value: value.to_string(),
+                secret: false,
+            }),
+            _ => error!(&format!(
+                "invalid variable \"{variable}\", expected name=value"
+            )),
+        }
+    }
+}
+
+/// Values for `{{name}}` placeholders in the URL, headers and body.
+#[derive(Default)]
+pub struct Variables {
+    values: HashMap<String, Variable>,
+}
+
+impl Variables {
+    /// Sets the variables, replacing earlier values with the same name.
+    pub fn extend(&mut self, variables: impl IntoIterator<Item = Variable>) {
+        self.values.extend(
+            variables
+                .into_iter()
+                .map(|variable| (variable.name.clone(), variable)),
+        );
+    }
+
+    /// The values of variables resolved from secret references.
+    pub fn secrets(&self) -> Vec<String> {
+        self.values
+            .values()
+            .filter(|variable| variable.secret)
+            .map(|variable| variable.value.clone())
+            .collect()
+    }
+
+    /// Replaces every `{{name}}` in `text`. Braces around anything that is not
+    /// a variable name, such as `{{ }}`, are kept as they are.
+    pub fn render(&self, text: &str) -> Result<String, Error> {
+        let mut rendered = String::with_capacity(text.len());
+        let mut undefined = BTreeSet::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let name = rest[start + 2..start + 2 + end].trim();
+            rendered.push_str(&rest[..start]);
+            match self.values.get(name) {
+                Some(variable) => rendered.push_str(&variable.value),
+                None => {
+                    if is_name(name) {
+                        undefined.insert(name);
+                    }
+                    rendered.push_str(&rest[start..start + 4 + end]);
+                }
+            }
+            rest = &rest[start + 4 + end..];
+        }
+        rendered.push_str(rest);
+
+        if !undefined.is_empty() {
+            let names: Vec<&str> = undefined.into_iter().collect();
+            let noun = if names.len() == 1 {
+                "variable"
+            } else {
+                "variables"
+            };
+            error!(&format!(
+                "undefined {noun} {}, set with --var, --env-file or --env",
+                names.join(", ")
+            ))
+        }
+        Ok(rendered)
+    }
+}
+
+/// Reads `NAME=value` lines from a dotenv file. Blank lines and lines starting
+/// with `#` are skipped, `export` is allowed and quotes around values are removed.
+pub fn parse_env_file(path: &PathBuf) -> Result<Vec<Variable>, Error> {
+    let content = match read_file(path) {
+        Ok(content) => content,
+        Err(why) => error!(&format!(
+            "can't read env file {}: {}",
+            path.display(),
+            why.message
+        )),
+    };
+    let mut variables = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Ok(mut variable) = Variable::try_from(line) else {
+            error!(&format!(
+                "invalid line {} in env file {}, expected NAME=value",
+                number + 1,
+                path.display()
+            ))
+        };
+        variable.value = unquote(variable.value.trim()).to_string();
+        variables.push(variable);
+    }
+    Ok(variables)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(unquoted) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return unquoted;
+        }
+    }
+    value
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(pairs: &[&str]) -> Variables {
+        let mut variables = Variables::default();
+        variables.extend(pairs.iter().map(|pair| Variable::try_from(pair).unwrap()));
+        variables
+    }
+
+    #[test]
+    fn test_render() {
+        let variables = variables(&["base_url=https://petstore.com", "id=42", "id=7"]);
+        assert_eq!(
+            variables.render("{{base_url}}/animals/{{ id }}").unwrap(),
+            "https://petstore.com/animals/7"
+        );
+        assert_eq!(
+            variables.render(r#"{"a":{"b":{}}} {{ }} {{"#).unwrap(),
+            r#"{"a":{"b":{}}} {{ }} {{"#
+        );
+    }
+
+    #[test]
+    fn test_undefined_variables() {
+        let err = variables(&["id=1"])
+            .render("{{base_url}}/{{id}}/{{token}}")
+            .err()
+            .unwrap();
+        assert!(err.message.contains("base_url, token"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_secrets() {
+        let mut variables = variables(&["token=public"]);
+        variables.extend([Variable {
+            name: "token".to_string(),
+            value: "s3cret".to_string(),
+            secret: true,
+        }]);
+        assert_eq!(variables.secrets(), ["s3cret"]);
+
+        variables.extend([Variable::try_from("token=public").unwrap()]);
+        assert!(variables.secrets().is_empty());
+    }
+
+    #[test]
+    fn test_parse_env_file() {
+        let path = std::env::temp_dir().join("hur_template_test.env");
+        std::fs::write(
+            &path,
+            "# petstore\nBASE_URL=https://petstore.com\n\nexport TOKEN=\"abc def\"\nEMPTY=\n",
+        )
+        .unwrap();
+        let variables = parse_env_file(&path).unwrap();
+        let pairs: Vec<(&str, &str)> = variables
+            .iter()
+            .map(|v| (v.name.as_str(), v.value.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                ("BASE_URL", "https://petstore.com"),
+                ("TOKEN", "abc def"),
+                ("EMPTY", "")
+            ]
+        );
+
+        std::fs::write(&path, "not a variable\n").unwrap();
+        assert!(parse_env_file(&path).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -8,17 +8,30 @@ macro_rules! error {
     };
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    Connect,
+    Timeout,
+    Other,
+}
+
 #[derive(Debug)]
 pub struct Error {
     pub message: String,
+    pub kind: ErrorKind,
 }
 
 impl std::error::Error for Error {}
 
 impl Error {
     pub fn new(message: &str) -> Error {
+        Error::with_kind(message, ErrorKind::Other)
+    }
+
+    pub fn with_kind(message: &str, kind: ErrorKind) -> Error {
         Error {
             message: String::from(message),
+            kind,
         }
     }
 }
@@ -31,48 +44,48 @@ impl std::fmt::Display for Error {
 
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
-        Error {
-            message: err.to_string(),
-        }
+        use std::io::ErrorKind as IoKind;
+        let kind = match err.kind() {
+            IoKind::TimedOut | IoKind::WouldBlock => ErrorKind::Timeout,
+            IoKind::ConnectionRefused
+            | IoKind::ConnectionReset
+            | IoKind::ConnectionAborted
+            | IoKind::NotConnected
+            | IoKind::AddrNotAvailable
+            | IoKind::HostUnreachable
+            | IoKind::NetworkUnreachable => ErrorKind::Connect,
+            _ => ErrorKind::Other,
+        };
+        Error::with_kind(&err.to_string(), kind)
     }
 }
 
 impl From<native_tls::Error> for Error {
     fn from(err: native_tls::Error) -> Self {
-        Error {
-            message: err.to_string(),
-        }
+        Error::new(&err.to_string())
     }
 }
 
 impl From<native_tls::HandshakeError<std::net::TcpStream>> for Error {
     fn from(err: native_tls::HandshakeError<std::net::TcpStream>) -> Self {
-        Error {
-            message: err.to_string(),
-        }
+        Error::new(&err.to_string())
     }
 }
 
 impl From<serde_json::error::Error> for Error {
     fn from(err: serde_json::error::Error) -> Self {
-        Error {
-            message: String::from("JSON input is invalid: ") + &err.to_string(),
-        }
+        Error::new(&(String::from("JSON input is invalid: ") + &err.to_string()))
     }
 }
 
 impl From<log::SetLoggerError> for Error {
     fn from(err: log::SetLoggerError) -> Self {
-        Error {
-            message: err.to_string(),
-        }
+        Error::new(&err.to_string())
     }
 }
 
 impl From<std::str::Utf8Error> for Error {
     fn from(err: std::str::Utf8Error) -> Self {
-        Error {
-            message: err.to_string(),
-        }
+        Error::new(&err.to_string())
     }
 }
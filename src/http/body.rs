@@ -0,0 +1,305 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use super::compression::Compression;
+use crate::error;
+use crate::error::Error;
+
+/// A reader that is consumed the first time the body is sent.
+#[derive(Clone)]
+pub struct Stream(Rc<RefCell<Option<Box<dyn Read>>>>);
+
+impl Stream {
+    pub fn new(reader: impl Read + 'static) -> Self {
+        Stream(Rc::new(RefCell::new(Some(Box::new(reader)))))
+    }
+}
+
+impl std::fmt::Debug for Stream {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Stream")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum BodyPart {
+    Bytes(Vec<u8>),
+    /// A file that is streamed from disk when the request is sent.
+    File(PathBuf),
+    /// A source of unknown length, such as stdin, sent with chunked encoding.
+    Stream(Stream),
+}
+
+/// A request body made of parts kept in memory and files read when the
+/// request is sent, so large uploads are never loaded into memory.
+#[derive(Debug, Clone)]
+pub struct Body {
+    parts: Vec<BodyPart>,
+    chunked: bool,
+}
+
+impl Body {
+    pub fn from_parts(parts: Vec<BodyPart>) -> Self {
+        Body {
+            parts,
+            chunked: false,
+        }
+    }
+
+    pub fn file(path: PathBuf) -> Self {
+        Body::from_parts(vec![BodyPart::File(path)])
+    }
+
+    pub fn stream(reader: impl Read + 'static) -> Self {
+        Body::from_parts(vec![BodyPart::Stream(Stream::new(reader))])
+    }
+
+    /// Sends the body with `Transfer-Encoding: chunked` even if its length is known.
+    pub fn set_chunked(&mut self) {
+        self.chunked = true;
+    }
+
+    /// Reads streamed parts into memory, so the body has a length and can be sent again.
+    pub fn buffer(&mut self) -> Result<(), Error> {
+        for part in &mut self.parts {
+            if let BodyPart::Stream(stream) = part {
+                let mut bytes = Vec::new();
+                if let Some(mut reader) = stream.0.borrow_mut().take() {
+                    reader.read_to_end(&mut bytes)?;
+                }
+                *part = BodyPart::Bytes(bytes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the content with its compressed form, which is kept in memory.
+    pub fn compress(&mut self, compression: Compression) -> Result<(), Error> {
+        let compressed = compression.compress(self)?;
+        self.parts = vec![BodyPart::Bytes(compressed)];
+        Ok(())
+    }
+
+    /// The body as text, if it is kept in memory.
+    #[cfg(test)]
+    pub fn as_text(&self) -> Option<String> {
+        let mut bytes = Vec::new();
+        for part in &self.parts {
+            match part {
+                BodyPart::Bytes(part) => bytes.extend_from_slice(part),
+                _ => return None,
+            }
+        }
+        String::from_utf8(bytes).ok()
+    }
+
+    /// Whether the body can be sent again, for retries and redirects.
+    pub fn is_replayable(&self) -> bool {
+        !self
+            .parts
+            .iter()
+            .any(|part| matches!(part, BodyPart::Stream(_)))
+    }
+
+    /// Whether the body can still be sent, which a streamed body only can once.
+    pub fn can_be_sent(&self) -> bool {
+        self.parts.iter().all(|part| match part {
+            BodyPart::Stream(stream) => stream.0.borrow().is_some(),
+            _ => true,
+        })
+    }
+
+    /// The length for `Content-Length`, or `None` if the body is sent chunked.
+    pub fn content_length(&self) -> Result<Option<u64>, Error> {
+        if self.chunked || !self.is_replayable() {
+            return Ok(None);
+        }
+        let mut len = 0;
+        for part in &self.parts {
+            len += match part {
+                BodyPart::Bytes(bytes) => bytes.len() as u64,
+                BodyPart::File(path) => std::fs::metadata(path)?.len(),
+                BodyPart::Stream(_) => 0,
+            };
+        }
+        Ok(Some(len))
+    }
+
+    /// Writes the body as sent on the wire, chunked if it has no content length.
+    pub fn write_encoded(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        if self.content_length()?.is_some() {
+            return self.write_to(writer);
+        }
+        let mut chunked = ChunkedWriter { inner: writer };
+        self.write_to(&mut chunked)?;
+        chunked.finish()
+    }
+
+    pub fn write_to(&self, writer: &mut dyn Write) -> Result<(), Error> {
+        for part in &self.parts {
+            match part {
+                BodyPart::Bytes(bytes) => writer.write_all(bytes)?,
+                BodyPart::File(path) => {
+                    let mut file = File::open(path)?;
+                    std::io::copy(&mut file, writer)?;
+                }
+                BodyPart::Stream(stream) => {
+                    let Some(mut reader) = stream.0.borrow_mut().take() else {
+                        error!("a streamed body can only be sent once")
+                    };
+                    std::io::copy(&mut reader, writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Writes every write as a chunk, RFC 9112 section 7.1
+struct ChunkedWriter<'a> {
+    inner: &'a mut dyn Write,
+}
+
+impl ChunkedWriter<'_> {
+    fn finish(self) -> Result<(), Error> {
+        self.inner.write_all(b"0\r\n\r\n")?;
+        Ok(())
+    }
+}
+
+impl Write for ChunkedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        write!(self.inner, "{:X}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl From<&str> for Body {
+    fn from(text: &str) -> Self {
+        Body::from(text.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Self {
+        Body::from(text.into_bytes())
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body::from_parts(vec![BodyPart::Bytes(bytes)])
+    }
+}
+
+// Files are shown by path and binary data by size in verbose output
+impl serde::Serialize for Body {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let text: String = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                BodyPart::Bytes(bytes) => match std::str::from_utf8(bytes) {
+                    Ok(text) => text.to_string(),
+                    Err(_) => format!("<{} bytes of binary data>", bytes.len()),
+                },
+                BodyPart::File(path) => format!("<{}>", path.display()),
+                BodyPart::Stream(_) => "<stream>".to_string(),
+            })
+            .collect();
+        serializer.serialize_str(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_parts_are_streamed() {
+        let path = std::env::temp_dir().join("hur_body_test.txt");
+        std::fs::write(&path, "file content").unwrap();
+        let body = Body::from_parts(vec![
+            BodyPart::Bytes(b"before ".to_vec()),
+            BodyPart::File(path.clone()),
+            BodyPart::Bytes(b" after".to_vec()),
+        ]);
+
+        let mut written = Vec::new();
+        body.write_to(&mut written).unwrap();
+
+        assert_eq!(written, b"before file content after");
+        assert_eq!(body.content_length().unwrap(), Some(written.len() as u64));
+        assert!(body.as_text().is_none());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_binary_body_in_output() {
+        let body = Body::from(vec![0xff, 0x00, 0xfe]);
+        assert_eq!(
+            serde_json::to_string(&body).unwrap(),
+            r#""<3 bytes of binary data>""#
+        );
+    }
+
+    #[test]
+    fn test_stream_is_chunked() {
+        let body = Body::from_parts(vec![
+            BodyPart::Bytes(b"hello ".to_vec()),
+            BodyPart::Stream(Stream::new(&b"world"[..])),
+        ]);
+
+        let mut written = Vec::new();
+        body.write_encoded(&mut written).unwrap();
+
+        assert_eq!(body.content_length().unwrap(), None);
+        assert!(!body.is_replayable());
+        assert_eq!(written, b"6\r\nhello \r\n5\r\nworld\r\n0\r\n\r\n");
+        assert!(!body.can_be_sent());
+        assert!(body.write_encoded(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_buffered_stream() {
+        let mut body = Body::stream(&b"hello"[..]);
+        body.buffer().unwrap();
+        assert_eq!(body.content_length().unwrap(), Some(5));
+        assert_eq!(body.as_text().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_compressed_length() {
+        let mut body = Body::from("a".repeat(1000));
+        body.compress(Compression::Gzip).unwrap();
+        let mut written = Vec::new();
+        body.write_to(&mut written).unwrap();
+        assert!(written.len() < 1000);
+        assert!(written.starts_with(b"\x1f\x8b"));
+        assert_eq!(body.content_length().unwrap(), Some(written.len() as u64));
+    }
+
+    #[test]
+    fn test_chunked_bytes() {
+        let mut body = Body::from("name=luffy");
+        body.set_chunked();
+        let mut written = Vec::new();
+        body.write_encoded(&mut written).unwrap();
+        assert_eq!(written, b"A\r\nname=luffy\r\n0\r\n\r\n");
+    }
+}
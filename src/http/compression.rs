@@ -0,0 +1,86 @@
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use super::body::Body;
+use crate::error::Error;
+
+// Buffer size, quality and window size for the brotli encoder
+const BROTLI_BUFFER: usize = 4096;
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+/// A `Content-Encoding` a request body can be compressed with.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Compression {
+    Gzip,
+    /// The zlib format, which is what `deflate` means in HTTP.
+    Deflate,
+    Br,
+}
+
+impl Compression {
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Deflate => "deflate",
+            Compression::Br => "br",
+        }
+    }
+
+    /// Compresses the content of the body into memory.
+    pub fn compress(&self, body: &Body) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+        match self {
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(&mut output, flate2::Compression::default());
+                body.write_to(&mut encoder)?;
+                encoder.finish()?;
+            }
+            Compression::Deflate => {
+                let mut encoder = ZlibEncoder::new(&mut output, flate2::Compression::default());
+                body.write_to(&mut encoder)?;
+                encoder.finish()?;
+            }
+            Compression::Br => {
+                let mut encoder = brotli::CompressorWriter::new(
+                    &mut output,
+                    BROTLI_BUFFER,
+                    BROTLI_QUALITY,
+                    BROTLI_WINDOW,
+                );
+                body.write_to(&mut encoder)?;
+                // Taking the writer out finishes the stream
+                encoder.into_inner();
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_round_trip() {
+        let input = "Straw Hat Pirates ".repeat(100);
+        let body = Body::from(input.as_str());
+        for compression in [Compression::Gzip, Compression::Deflate, Compression::Br] {
+            let compressed = compression.compress(&body).unwrap();
+            assert!(compressed.len() < input.len(), "{compression:?}");
+
+            let mut decompressed = String::new();
+            match compression {
+                Compression::Gzip => {
+                    flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut decompressed)
+                }
+                Compression::Deflate => flate2::read::ZlibDecoder::new(&compressed[..])
+                    .read_to_string(&mut decompressed),
+                Compression::Br => brotli::Decompressor::new(&compressed[..], BROTLI_BUFFER)
+                    .read_to_string(&mut decompressed),
+            }
+            .unwrap();
+            assert_eq!(decompressed, input, "{compression:?}");
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::headers::Headers;
+use crate::date::parse_http_date;
+use crate::error;
+use crate::error::Error;
+
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// Only sent to the exact host that set it, when no Domain attribute was given.
+    pub host_only: bool,
+    pub path: String,
+    /// Unix timestamp in seconds, `None` for session cookies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<u64>,
+    pub secure: bool,
+    pub http_only: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Parses a Set-Cookie header value received from `url`, following RFC 6265.
+    pub fn parse(set_cookie: &str, url: &Url) -> Option<Cookie> {
+        let mut attributes = set_cookie.split(';');
+        let (name, value) = attributes.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let host = url.host_str()?.to_lowercase();
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(url),
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        };
+
+        let mut max_age = None;
+        for attribute in attributes {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+            match key.to_lowercase().as_str() {
+                "expires" => {
+                    if let Some(date) = parse_http_date(value) {
+                        cookie.expires = Some(unix_seconds(date));
+                    }
+                }
+                "max-age" => {
+                    if let Ok(seconds) = value.parse::<i64>() {
+                        max_age = Some(seconds);
+                    }
+                }
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+                    if !domain_matches(&host, &domain) || !domain.contains('.') && domain != host {
+                        log::debug!("Rejected cookie {name} for domain {domain} from {host}");
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => {
+                    cookie.same_site = match value.to_lowercase().as_str() {
+                        "strict" => Some(SameSite::Strict),
+                        "lax" => Some(SameSite::Lax),
+                        "none" => Some(SameSite::None),
+                        _ => None,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Max-Age takes precedence over Expires
+        if let Some(seconds) = max_age {
+            cookie.expires = Some(if seconds <= 0 {
+                0
+            } else {
+                now().saturating_add(seconds as u64)
+            });
+        }
+
+        Some(cookie)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= now())
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host.to_lowercase(),
+            None => return false,
+        };
+        let domain_ok = match self.host_only {
+            true => host == self.domain,
+            false => domain_matches(&host, &self.domain),
+        };
+        domain_ok
+            && path_matches(url.path(), &self.path)
+            && (!self.secure || url.scheme() == "https")
+            && !self.is_expired()
+    }
+
+    fn same_cookie(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+
+    fn to_netscape(&self) -> String {
+        let mut domain = match self.host_only {
+            true => self.domain.clone(),
+            false => format!(".{}", self.domain),
+        };
+        if self.http_only {
+            domain.insert_str(0, HTTP_ONLY_PREFIX);
+        }
+        format!(
+            "{domain}\t{subdomains}\t{path}\t{secure}\t{expires}\t{name}\t{value}",
+            subdomains = netscape_bool(!self.host_only),
+            path = self.path,
+            secure = netscape_bool(self.secure),
+            expires = self.expires.unwrap_or(0),
+            name = self.name,
+            value = self.value,
+        )
+    }
+
+    fn from_netscape(line: &str) -> Result<Cookie, Error> {
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != 7 {
+            error!(&format!("invalid cookie file line \"{line}\""))
+        }
+        let expires = match fields[4].parse::<u64>() {
+            Ok(0) => None,
+            Ok(expires) => Some(expires),
+            Err(_) => error!(&format!("invalid cookie expiry \"{}\"", fields[4])),
+        };
+        Ok(Cookie {
+            name: fields[5].to_string(),
+            value: fields[6].to_string(),
+            domain: fields[0].trim_start_matches('.').to_lowercase(),
+            host_only: !fields[1].eq_ignore_ascii_case("TRUE"),
+            path: fields[2].to_string(),
+            expires,
+            secure: fields[3].eq_ignore_ascii_case("TRUE"),
+            http_only,
+            same_site: None,
+        })
+    }
+}
+
+/// In-memory cookie store. SameSite is kept so it can be persisted, but not enforced
+/// since there is no browsing context to compare sites with.
+#[derive(Default, Debug)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        CookieJar {
+            cookies: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, cookie: Cookie) {
+        self.cookies.retain(|c| !c.same_cookie(&cookie));
+        if !cookie.is_expired() {
+            self.cookies.push(cookie);
+        }
+    }
+
+    /// Stores the cookies from the Set-Cookie headers of a response to `url`.
+    pub fn store(&mut self, url: &Url, headers: &Headers) {
+        if let Some(set_cookies) = headers.get("Set-Cookie") {
+            for set_cookie in set_cookies {
+                if let Some(cookie) = Cookie::parse(set_cookie, url) {
+                    log::debug!("Storing cookie {} for {}", cookie.name, cookie.domain);
+                    self.add(cookie);
+                }
+            }
+        }
+    }
+
+    /// The value of the Cookie header to send to `url`, if any cookies match.
+    pub fn header(&self, url: &Url) -> Option<String> {
+        let mut matching: Vec<&Cookie> = self.cookies.iter().filter(|c| c.matches(url)).collect();
+        if matching.is_empty() {
+            return None;
+        }
+        // Longer paths first, otherwise in the order they were stored
+        matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+        let pairs: Vec<String> = matching
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+
+    pub fn cookies(&self) -> Vec<&Cookie> {
+        self.cookies.iter().filter(|c| !c.is_expired()).collect()
+    }
+
+    pub fn from_netscape(content: &str) -> Result<CookieJar, Error> {
+        let mut jar = CookieJar::new();
+        for line in content.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty()
+                || line.starts_with('#') && !line.starts_with(HTTP_ONLY_PREFIX)
+            {
+                continue;
+            }
+            jar.add(Cookie::from_netscape(line)?);
+        }
+        Ok(jar)
+    }
+
+    pub fn to_netscape(&self) -> String {
+        let mut content = String::from("# Netscape HTTP Cookie File\n# Written by hur\n\n");
+        for cookie in self.cookies() {
+            content.push_str(&cookie.to_netscape());
+            content.push('\n');
+        }
+        content
+    }
+}
+
+fn netscape_bool(value: bool) -> &'static str {
+    match value {
+        true => "TRUE",
+        false => "FALSE",
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{domain}"))
+}
+
+fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/'))
+}
+
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn now() -> u64 {
+    unix_seconds(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_parse_cookie() {
+        let cookie = Cookie::parse(
+            "id=a3fWa; Domain=.example.com; Path=/docs; Secure; HttpOnly; SameSite=Lax",
+            &url("https://www.example.com/docs/web"),
+        )
+        .unwrap();
+        assert_eq!(cookie.name, "id");
+        assert_eq!(cookie.value, "a3fWa");
+        assert_eq!(cookie.domain, "example.com");
+        assert!(!cookie.host_only);
+        assert_eq!(cookie.path, "/docs");
+        assert!(cookie.secure);
+        assert!(cookie.http_only);
+        assert_eq!(cookie.same_site, Some(SameSite::Lax));
+    }
+
+    #[test]
+    fn test_parse_cookie_defaults() {
+        let cookie = Cookie::parse("id=1", &url("http://example.com/a/b")).unwrap();
+        assert_eq!(cookie.domain, "example.com");
+        assert!(cookie.host_only);
+        assert_eq!(cookie.path, "/a");
+        assert_eq!(cookie.expires, None);
+    }
+
+    #[test]
+    fn test_reject_foreign_domain() {
+        assert!(Cookie::parse("id=1; Domain=other.com", &url("http://example.com")).is_none());
+        assert!(Cookie::parse("id=1; Domain=com", &url("http://example.com")).is_none());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let cookie = Cookie::parse(
+            "id=1; Expires=Wed, 21-Oct-2015 07:28:00 GMT",
+            &url("http://example.com"),
+        )
+        .unwrap();
+        assert_eq!(cookie.expires, Some(1445412480));
+        assert!(cookie.is_expired());
+
+        let cookie = Cookie::parse(
+            "id=1; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Max-Age=3600",
+            &url("http://example.com"),
+        )
+        .unwrap();
+        assert!(!cookie.is_expired());
+    }
+
+    #[test]
+    fn test_jar_header() {
+        let mut jar = CookieJar::new();
+        let mut headers = Headers::new();
+        headers.add("Set-Cookie", "a=1; Path=/");
+        headers.add("Set-Cookie", "b=2; Path=/api");
+        headers.add("Set-Cookie", "c=3; Secure");
+        headers.add("Set-Cookie", "d=4; Domain=example.com");
+        jar.store(&url("http://www.example.com/"), &headers);
+
+        assert_eq!(
+            jar.header(&url("http://www.example.com/api/users")),
+            Some("b=2; a=1; d=4".to_string())
+        );
+        assert_eq!(
+            jar.header(&url("https://www.example.com/")),
+            Some("a=1; c=3; d=4".to_string())
+        );
+        assert_eq!(
+            jar.header(&url("http://api.example.com/apis")),
+            Some("d=4".to_string())
+        );
+        assert_eq!(jar.header(&url("http://other.com/")), None);
+    }
+
+    #[test]
+    fn test_jar_replace_and_delete() {
+        let mut jar = CookieJar::new();
+        let example = url("http://example.com/");
+        jar.add(Cookie::parse("a=1", &example).unwrap());
+        jar.add(Cookie::parse("a=2", &example).unwrap());
+        assert_eq!(jar.header(&example), Some("a=2".to_string()));
+        jar.add(Cookie::parse("a=2; Max-Age=0", &example).unwrap());
+        assert_eq!(jar.header(&example), None);
+    }
+
+    #[test]
+    fn test_netscape_round_trip() {
+        let mut jar = CookieJar::new();
+        let example = url("https://example.com/");
+        jar.add(Cookie::parse("a=1; Domain=example.com; Secure; HttpOnly", &example).unwrap());
+        jar.add(Cookie::parse("b=2; Max-Age=3600", &example).unwrap());
+        let content = jar.to_netscape();
+        assert!(content.contains("#HttpOnly_.example.com\tTRUE\t/\tTRUE\t0\ta\t1"));
+
+        let loaded = CookieJar::from_netscape(&content).unwrap();
+        assert_eq!(loaded.cookies(), jar.cookies());
+    }
+
+    #[test]
+    fn test_path_matches() {
+        assert!(path_matches("/", "/"));
+        assert!(path_matches("/docs/web", "/docs"));
+        assert!(path_matches("/docs/", "/docs/"));
+        assert!(!path_matches("/documents", "/docs"));
+    }
+}
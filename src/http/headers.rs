@@ -1,8 +1,9 @@
-use std::collections::{HashMap, hash_map};
+use std::collections::{HashMap, HashSet, hash_map};
 use std::fmt::Display;
 
 use serde::ser::SerializeMap;
 
+use super::redact::{REDACTED, redaction};
 use crate::error;
 use crate::error::Error;
 
@@ -23,6 +24,14 @@ impl Header {
             value: splits[1].trim().to_string(),
         })
     }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
 }
 
 #[test]
@@ -52,9 +61,12 @@ fn test_invalid_headers() {
     }
 }
 
-#[derive(Debug)]
 pub struct Headers {
     internal_headers: HashMap<String, Vec<String>>,
+    // Headers holding secrets, which are always redacted when serialized or displayed
+    secrets: HashSet<String>,
+    // Headers given without a value, which are left out of the request
+    suppressed: HashSet<String>,
 }
 
 impl serde::Serialize for Headers {
@@ -63,7 +75,7 @@ impl serde::Serialize for Headers {
         S: serde::Serializer,
     {
         let mut map = serializer.serialize_map(Some(self.internal_headers.len()))?;
-        for (k, v) in &self.internal_headers {
+        for (k, v) in &self.redacted() {
             map.serialize_entry(&k, &v)?;
         }
         map.end()
@@ -74,9 +86,55 @@ impl Headers {
     pub fn new() -> Self {
         Headers {
             internal_headers: HashMap::<String, Vec<String>>::new(),
+            secrets: HashSet::new(),
+            suppressed: HashSet::new(),
         }
     }
 
+    /// Adds a header whose value is redacted in verbose output and logs, even if
+    /// it is not in the redacted headers.
+    pub fn add_secret(&mut self, key: &str, value: &str) {
+        self.add(key, value);
+        self.secrets.insert(key.to_lowercase());
+    }
+
+    pub fn is_secret(&self, key: &str) -> bool {
+        self.secrets.contains(key.to_lowercase().as_str())
+    }
+
+    /// Removes headers given without a value, like `User-Agent:`, and keeps them
+    /// suppressed so that defaults for them are not added.
+    pub fn suppress_empty(&mut self) {
+        let empty: Vec<String> = self
+            .internal_headers
+            .iter()
+            .filter(|(_, values)| values.iter().all(|value| value.trim().is_empty()))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in empty {
+            self.remove(&key);
+            self.suppressed.insert(key);
+        }
+    }
+
+    pub fn is_suppressed(&self, key: &str) -> bool {
+        self.suppressed.contains(key.to_lowercase().as_str())
+    }
+
+    fn redacted(&self) -> HashMap<&String, Vec<&str>> {
+        let redaction = redaction();
+        self.internal_headers
+            .iter()
+            .map(|(key, values)| {
+                let values = match redaction.is_redacted(key, self.secrets.contains(key)) {
+                    true => values.iter().map(|_| REDACTED).collect(),
+                    false => values.iter().map(|v| v.as_str()).collect(),
+                };
+                (key, values)
+            })
+            .collect()
+    }
+
     pub fn add(&mut self, key: &str, value: &str) {
         let key = key.to_lowercase();
         if self.internal_headers.contains_key(key.as_str()) {
@@ -91,6 +149,8 @@ impl Headers {
     }
 
     pub fn append(&mut self, other: Headers) {
+        self.secrets.extend(other.secrets);
+        self.suppressed.extend(other.suppressed);
         for (key, val) in other.internal_headers {
             let key = key.to_lowercase();
             match key.as_str() {
@@ -106,6 +166,11 @@ impl Headers {
         }
     }
 
+    pub fn remove(&mut self, header: &str) -> Option<Vec<String>> {
+        self.secrets.remove(header.to_lowercase().as_str());
+        self.internal_headers.remove(header.to_lowercase().as_str())
+    }
+
     pub fn get(&self, header: &str) -> Option<&Vec<String>> {
         self.internal_headers
             .get(header.to_lowercase().as_str())
@@ -118,7 +183,7 @@ impl Headers {
         headers.unwrap().first().cloned()
     }
 
-    pub fn iter(&self) -> HeaderIterator {
+    pub fn iter(&self) -> HeaderIterator<'_> {
         HeaderIterator {
             iterator: self.internal_headers.iter(),
         }
@@ -127,7 +192,13 @@ impl Headers {
 
 impl Display for Headers {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}", self.internal_headers)
+        write!(f, "{:?}", self.redacted())
+    }
+}
+
+impl std::fmt::Debug for Headers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_map().entries(self.redacted()).finish()
     }
 }
 
@@ -171,3 +242,23 @@ impl std::iter::IntoIterator for Headers {
         self.internal_headers.into_iter()
     }
 }
+
+#[test]
+fn test_secret_headers_are_redacted() {
+    let mut headers = Headers::new();
+    headers.add("Accept", "*/*");
+    headers.add_secret("X-Petstore-Key", "s3cret");
+    headers.add("Authorization", "Bearer t0ken");
+
+    let json = serde_json::to_string(&headers).unwrap();
+    let display = headers.to_string();
+
+    assert!(!json.contains("s3cret") && json.contains("***"));
+    assert!(!json.contains("t0ken") && !display.contains("t0ken"));
+    assert!(!display.contains("s3cret"));
+    assert!(json.contains("*/*"));
+    assert_eq!(
+        headers.get_first("x-petstore-key"),
+        Some("s3cret".to_string())
+    );
+}
@@ -0,0 +1,181 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::Error;
+
+// How much of a file is read to look for a signature
+const SNIFF_LEN: u64 = 512;
+
+// Extensions that say nothing about the content, so the content is sniffed instead
+const AMBIGUOUS_EXTENSIONS: [&str; 3] = ["bin", "dat", "raw"];
+
+const EXTENSIONS: [(&str, &str); 41] = [
+    ("json", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("ndjson", "application/x-ndjson"),
+    ("xml", "application/xml"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("toml", "application/toml"),
+    ("graphql", "application/graphql"),
+    ("proto", "text/plain"),
+    ("pb", "application/x-protobuf"),
+    ("protobuf", "application/x-protobuf"),
+    ("wasm", "application/wasm"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("bmp", "image/bmp"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tgz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("7z", "application/x-7z-compressed"),
+    ("br", "application/x-brotli"),
+    ("zst", "application/zstd"),
+];
+
+const SIGNATURES: [(&[u8], &str); 11] = [
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"PK\x05\x06", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"\x00asm", "application/wasm"),
+];
+
+/// The media type for a file extension, compared case-insensitively.
+pub fn from_extension(extension: &str) -> Option<&'static str> {
+    let extension = extension.to_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|(_, mime)| *mime)
+}
+
+/// Guesses the media type from the first bytes of the content.
+pub fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+    {
+        return Some(mime);
+    }
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return Some("image/webp");
+    }
+    if bytes.get(257..262) == Some(b"ustar") {
+        return Some("application/x-tar");
+    }
+
+    // The sniffed bytes can end in the middle of a character
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..err.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    let start = text.trim_start_matches('\u{feff}').trim_start();
+    let lower: String = start.chars().take(16).collect::<String>().to_lowercase();
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        Some("text/html")
+    } else if lower.starts_with("<svg") {
+        Some("image/svg+xml")
+    } else if lower.starts_with("<?xml") {
+        Some("application/xml")
+    } else if looks_like_json(start) {
+        Some("application/json")
+    } else {
+        None
+    }
+}
+
+// Only the start of a file is sniffed, so an object is recognised by its first key
+fn looks_like_json(text: &str) -> bool {
+    let object_key = text
+        .strip_prefix('{')
+        .is_some_and(|rest| rest.trim_start().starts_with(['"', '}']));
+    object_key || (text.starts_with('[') && serde_json::from_str::<serde_json::Value>(text).is_ok())
+}
+
+/// The media type of a file, from its extension, or its content if the
+/// extension is missing, unknown or ambiguous.
+pub fn detect(path: &Path) -> Result<Option<&'static str>, Error> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    if let Some(extension) = &extension
+        && !AMBIGUOUS_EXTENSIONS.contains(&extension.as_str())
+        && let Some(mime) = from_extension(extension)
+    {
+        return Ok(Some(mime));
+    }
+
+    let mut bytes = Vec::new();
+    File::open(path)?.take(SNIFF_LEN).read_to_end(&mut bytes)?;
+    Ok(sniff(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(from_extension("json"), Some("application/json"));
+        assert_eq!(from_extension("CSV"), Some("text/csv"));
+        assert_eq!(from_extension("pb"), Some("application/x-protobuf"));
+        assert_eq!(from_extension("unknown"), None);
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0"), Some("image/png"));
+        assert_eq!(sniff(b"%PDF-1.7"), Some("application/pdf"));
+        assert_eq!(sniff(b"PK\x03\x04rest"), Some("application/zip"));
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"  <?xml version=\"1.0\"?>"), Some("application/xml"));
+        assert_eq!(sniff(b"<!DOCTYPE html><html>"), Some("text/html"));
+        assert_eq!(sniff(br#"{"name": "Luffy"}"#), Some("application/json"));
+        assert_eq!(sniff(b"{not json"), None);
+        assert_eq!(sniff(b"plain text"), None);
+        assert_eq!(sniff(&[0xff, 0x00, 0xfe]), None);
+    }
+
+    #[test]
+    fn test_detect() {
+        let dir = std::env::temp_dir();
+        let named = dir.join("hur_mime_test.yaml");
+        let ambiguous = dir.join("hur_mime_test.bin");
+        std::fs::write(&named, "name: Luffy").unwrap();
+        std::fs::write(&ambiguous, b"%PDF-1.7").unwrap();
+
+        assert_eq!(detect(&named).unwrap(), Some("application/yaml"));
+        assert_eq!(detect(&ambiguous).unwrap(), Some("application/pdf"));
+        let _ = std::fs::remove_file(named);
+        let _ = std::fs::remove_file(ambiguous);
+    }
+}
@@ -2,11 +2,17 @@ use crate::error;
 use crate::error::Error;
 use std::convert::TryFrom;
 
+pub mod body;
+pub mod compression;
+pub mod cookies;
 pub mod headers;
+pub mod mime;
+pub mod multipart;
+pub mod redact;
 pub mod request;
 pub mod response;
 
-#[derive(serde::Serialize, Debug, Clone, clap::ValueEnum)]
+#[derive(serde::Serialize, Debug, Clone, PartialEq, clap::ValueEnum)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Method {
     Get,
@@ -20,6 +26,20 @@ pub enum Method {
     Connect,
 }
 
+impl Method {
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Method::Get
+                | Method::Head
+                | Method::Put
+                | Method::Delete
+                | Method::Options
+                | Method::Trace
+        )
+    }
+}
+
 impl std::fmt::Display for Method {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{self:?}")
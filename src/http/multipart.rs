@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+
+use super::body::{Body, BodyPart};
+use super::mime;
+use crate::error;
+use crate::error::Error;
+use crate::random::random_hex;
+
+const DEFAULT_FILE_TYPE: &str = "application/octet-stream";
+
+#[derive(Debug, Clone)]
+enum FieldValue {
+    Text(String),
+    File {
+        path: PathBuf,
+        content_type: Option<String>,
+        filename: String,
+    },
+}
+
+/// A `multipart/form-data` field, given as `name=value` or
+/// `name=@path;type=mime;filename=name`. Without `type`, the type of a file is detected.
+#[derive(Debug, Clone)]
+pub struct MultipartField {
+    name: String,
+    value: FieldValue,
+}
+
+impl MultipartField {
+    pub fn try_from(field: &str) -> Result<Self, Error> {
+        let (name, value) = match field.split_once('=') {
+            Some((name, value)) if !name.is_empty() => (name.to_string(), value),
+            _ => error!(&format!(
+                "invalid multipart field \"{field}\", expected name=value or name=@path"
+            )),
+        };
+        let Some(file) = value.strip_prefix('@') else {
+            return Ok(MultipartField {
+                name,
+                value: FieldValue::Text(value.to_string()),
+            });
+        };
+
+        let mut params = file.split(';');
+        let path = PathBuf::from(params.next().unwrap_or_default());
+        if !path.is_file() {
+            error!(&format!("multipart file {} does not exist", path.display()))
+        }
+        let mut content_type = mime::detect(&path)?.map(String::from);
+        let mut filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for param in params {
+            match param.trim().split_once('=') {
+                Some(("type", value)) => content_type = Some(value.to_string()),
+                Some(("filename", value)) => filename = value.to_string(),
+                _ => error!(&format!("invalid multipart file parameter \"{param}\"")),
+            }
+        }
+        Ok(MultipartField {
+            name,
+            value: FieldValue::File {
+                path,
+                content_type,
+                filename,
+            },
+        })
+    }
+
+    /// Applies `render` to the name and a text value. Files are streamed, so
+    /// their content is left as it is.
+    pub fn render(&self, render: impl Fn(&str) -> Result<String, Error>) -> Result<Self, Error> {
+        let value = match &self.value {
+            FieldValue::Text(text) => FieldValue::Text(render(text)?),
+            file => file.clone(),
+        };
+        Ok(MultipartField {
+            name: render(&self.name)?,
+            value,
+        })
+    }
+}
+
+/// A `multipart/form-data` body, RFC 7578. File contents are streamed from disk.
+pub struct Multipart {
+    boundary: String,
+    fields: Vec<MultipartField>,
+}
+
+impl Multipart {
+    pub fn new(fields: Vec<MultipartField>) -> Self {
+        Multipart {
+            boundary: format!("----------------hur{}", random_hex(24)),
+            fields,
+        }
+    }
+
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    pub fn body(&self) -> Body {
+        let mut parts = Vec::new();
+        for field in &self.fields {
+            let mut head = format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+                self.boundary,
+                escape(&field.name)
+            );
+            match &field.value {
+                FieldValue::Text(text) => {
+                    head.push_str("\r\n\r\n");
+                    head.push_str(text);
+                    head.push_str("\r\n");
+                    parts.push(BodyPart::Bytes(head.into_bytes()));
+                }
+                FieldValue::File {
+                    path,
+                    content_type,
+                    filename,
+                } => {
+                    head.push_str(&format!(
+                        "; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        escape(filename),
+                        content_type.as_deref().unwrap_or(DEFAULT_FILE_TYPE)
+                    ));
+                    parts.push(BodyPart::Bytes(head.into_bytes()));
+                    parts.push(BodyPart::File(path.clone()));
+                    parts.push(BodyPart::Bytes(b"\r\n".to_vec()));
+                }
+            }
+        }
+        parts.push(BodyPart::Bytes(
+            format!("--{}--\r\n", self.boundary).into_bytes(),
+        ));
+        Body::from_parts(parts)
+    }
+}
+
+// Quotes and line breaks in names are percent encoded, as browsers do
+fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipart_body() {
+        let path = std::env::temp_dir().join("hur_multipart_test.txt");
+        std::fs::write(&path, "file content").unwrap();
+        let multipart = Multipart {
+            boundary: "boundary".to_string(),
+            fields: vec![
+                MultipartField::try_from("name=Luffy").unwrap(),
+                MultipartField::try_from(&format!(
+                    "poster=@{};type=text/plain;filename=wanted.txt",
+                    path.display()
+                ))
+                .unwrap(),
+            ],
+        };
+
+        let body = multipart.body();
+        let mut written = Vec::new();
+        body.write_to(&mut written).unwrap();
+
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "--boundary\r\n\
+             Content-Disposition: form-data; name=\"name\"\r\n\r\n\
+             Luffy\r\n\
+             --boundary\r\n\
+             Content-Disposition: form-data; name=\"poster\"; filename=\"wanted.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\n\
+             file content\r\n\
+             --boundary--\r\n"
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_render() {
+        let upper = |text: &str| Ok(text.to_uppercase());
+        let field = MultipartField::try_from("name=Luffy").unwrap();
+        let rendered = field.render(upper).unwrap();
+        assert_eq!(rendered.name, "NAME");
+        assert!(matches!(rendered.value, FieldValue::Text(text) if text == "LUFFY"));
+
+        let path = std::env::temp_dir().join("hur_multipart_render_test.txt");
+        std::fs::write(&path, "file content").unwrap();
+        let field = MultipartField::try_from(&format!("poster=@{}", path.display())).unwrap();
+        let rendered = field.render(upper).unwrap();
+        assert!(
+            matches!(rendered.value, FieldValue::File { path: rendered, .. } if rendered == path)
+        );
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_detected_file_type() {
+        let path = std::env::temp_dir().join("hur_multipart_type_test.csv");
+        std::fs::write(&path, "name,bounty").unwrap();
+        let detected = MultipartField::try_from(&format!("crew=@{}", path.display())).unwrap();
+        let given =
+            MultipartField::try_from(&format!("crew=@{};type=text/plain", path.display())).unwrap();
+
+        for (field, expected) in [(detected, "text/csv"), (given, "text/plain")] {
+            let FieldValue::File { content_type, .. } = field.value else {
+                panic!("expected a file field")
+            };
+            assert_eq!(content_type.as_deref(), Some(expected));
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        assert!(MultipartField::try_from("novalue").is_err());
+        assert!(MultipartField::try_from("file=@/no/such/file").is_err());
+        let path = std::env::temp_dir().join("hur_multipart_param_test.txt");
+        std::fs::write(&path, "").unwrap();
+        assert!(MultipartField::try_from(&format!("file=@{};size=1", path.display())).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+/// Headers whose values are redacted unless `--no-redact` is given.
+pub const DEFAULT_REDACTED_HEADERS: [&str; 6] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-amz-security-token",
+];
+
+pub const REDACTED: &str = "***";
+
+static REDACTION: OnceLock<Redaction> = OnceLock::new();
+
+/// Which header values and secrets are masked in verbose output and debug logs.
+#[derive(Debug)]
+pub struct Redaction {
+    enabled: bool,
+    headers: HashSet<String>,
+    secrets: Vec<String>,
+}
+
+impl Redaction {
+    pub fn new(extra_headers: &[String], enabled: bool) -> Self {
+        let headers = DEFAULT_REDACTED_HEADERS
+            .iter()
+            .map(|h| h.to_string())
+            .chain(extra_headers.iter().map(|h| h.trim().to_lowercase()))
+            .collect();
+        Redaction {
+            enabled,
+            headers,
+            secrets: Vec::new(),
+        }
+    }
+
+    /// Also masks these values wherever they appear in text, as they are, URL
+    /// encoded or JSON escaped.
+    pub fn with_secrets(mut self, secrets: &[String]) -> Self {
+        for secret in secrets.iter().filter(|secret| !secret.is_empty()) {
+            let form_encoded: String =
+                url::form_urlencoded::byte_serialize(secret.as_bytes()).collect();
+            let json_escaped = serde_json::to_string(secret).unwrap_or_default();
+            let json_escaped = json_escaped.trim_matches('"');
+            self.secrets.extend([
+                secret.clone(),
+                form_encoded.replace('+', "%20"),
+                form_encoded,
+                json_escaped.to_string(),
+            ]);
+        }
+        // Longer forms first, so that no part of them is left after a shorter one
+        self.secrets
+            .sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        self.secrets.dedup();
+        self
+    }
+
+    /// Whether the value of `header` should be masked. Headers marked as secret
+    /// are always masked, unless redaction is disabled.
+    pub fn is_redacted(&self, header: &str, secret: bool) -> bool {
+        self.enabled && (secret || self.headers.contains(header.to_lowercase().as_str()))
+    }
+
+    /// Masks the secrets in `text`, such as a URL or body with rendered variables.
+    pub fn redact_text(&self, text: &str) -> String {
+        if !self.enabled {
+            return text.to_string();
+        }
+        self.secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+    }
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction::new(&[], true)
+    }
+}
+
+/// Sets the redaction used for the rest of the program. Only the first call has an effect.
+pub fn configure(redaction: Redaction) {
+    if REDACTION.set(redaction).is_err() {
+        log::warn!("Redaction is already configured");
+    }
+}
+
+pub fn redaction() -> &'static Redaction {
+    REDACTION.get_or_init(Redaction::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_headers() {
+        let redaction = Redaction::default();
+        assert!(redaction.is_redacted("Authorization", false));
+        assert!(redaction.is_redacted("set-cookie", false));
+        assert!(!redaction.is_redacted("accept", false));
+        assert!(redaction.is_redacted("x-custom", true));
+    }
+
+    #[test]
+    fn test_extra_headers() {
+        let redaction = Redaction::new(&["X-Session-Id ".to_string()], true);
+        assert!(redaction.is_redacted("x-session-id", false));
+        assert!(redaction.is_redacted("cookie", false));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let redaction = Redaction::new(&["x-session-id".to_string()], false)
+            .with_secrets(&["s3cret".to_string()]);
+        assert!(!redaction.is_redacted("authorization", false));
+        assert!(!redaction.is_redacted("x-custom", true));
+        assert_eq!(redaction.redact_text("key=s3cret"), "key=s3cret");
+    }
+
+    #[test]
+    fn test_secrets() {
+        let redaction = Redaction::default().with_secrets(&["a b\"c".to_string(), String::new()]);
+        assert_eq!(
+            redaction.redact_text("/animals?key=a+b%22c&id=1"),
+            "/animals?key=***&id=1"
+        );
+        assert_eq!(redaction.redact_text("/a%20b%22c/"), "/***/");
+        assert_eq!(
+            redaction.redact_text(r#"{"token":"a b\"c"}"#),
+            r#"{"token":"***"}"#
+        );
+        assert_eq!(redaction.redact_text("a b"), "a b");
+    }
+}
@@ -1,14 +1,20 @@
 use std::convert::TryFrom;
 use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
 
+use super::body::Body;
 use super::headers::Headers;
 use super::{Method, Scheme};
 use serde::Serialize;
+use serde::ser::SerializeMap;
 use url::Url;
 
 use crate::error::Error;
 use crate::proxy::should_proxy;
 
+pub const DEFAULT_TIMEOUT: u64 = 10;
+pub const DEFAULT_EXPECT_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[derive(Serialize)]
 pub struct Request {
     #[serde(skip)]
@@ -25,11 +31,17 @@ pub struct Request {
     path: String,
     pub headers: Headers,
     #[serde(skip_serializing_if = "Option::is_none")]
-    body: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<Body>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_query"
+    )]
     query: Option<String>,
     #[serde(skip)]
     pub timeout: u64,
+    /// How long to wait for `100 Continue` when sending `Expect: 100-continue`.
+    #[serde(skip)]
+    pub expect_timeout: Duration,
 }
 
 impl Request {
@@ -58,7 +70,8 @@ impl Request {
             body: None,
             query: url.query().map_or_else(|| None, |s| Some(String::from(s))),
             url,
-            timeout: timeout.unwrap_or(10),
+            timeout: timeout.unwrap_or(DEFAULT_TIMEOUT),
+            expect_timeout: DEFAULT_EXPECT_TIMEOUT,
         })
     }
 
@@ -66,30 +79,45 @@ impl Request {
         url: Url,
         method: Method,
         headers: Headers,
-        body: &str,
+        body: impl Into<Body>,
         timeout: Option<u64>,
     ) -> Result<Request, Error> {
+        let body = body.into();
         let mut request = Request::new(url, method, headers, timeout)?;
-        request.body = Some(body.to_string());
-        request
-            .headers
-            .add("Content-Length", &body.len().to_string());
+        match body.content_length()? {
+            Some(len) => request.headers.add("Content-Length", &len.to_string()),
+            None => request.headers.add("Transfer-Encoding", "chunked"),
+        }
+        request.body = Some(body);
         Ok(request)
     }
 
-    pub fn build(&self) -> String {
+    pub fn body(&self) -> Option<&Body> {
+        self.body.as_ref()
+    }
+
+    /// The path and query of the request, as used for the request line.
+    pub fn target(&self) -> String {
+        match &self.query {
+            Some(query) => format!("{}?{query}", self.path),
+            None => self.path.clone(),
+        }
+    }
+
+    /// The request line and headers. The body is written after them by the connector.
+    pub fn build(&self) -> Vec<u8> {
+        // Proxies get the absolute URL, the query is added to the target after it
         let path = match (self.proxy, &self.scheme) {
-            (true, Scheme::Http) => &self.full_path,
+            (true, Scheme::Http) => &self.url[..url::Position::AfterPath],
             _ => &self.path,
         };
-        self.build_request(path)
+        self.build_request(path).into_bytes()
     }
 
     fn build_request(&self, path: &str) -> String {
         let mut message = self.make_status_line(path);
         self.add_headers(&mut message);
-        self.add_body(&mut message);
-        message.push_str("\r\n\r\n");
+        message.push_str("\r\n");
         message
     }
 
@@ -106,13 +134,6 @@ impl Request {
         )
     }
 
-    fn add_body(&self, message: &mut String) {
-        if let Some(body) = &self.body {
-            message.push_str("\r\n");
-            message.push_str(body);
-        }
-    }
-
     fn add_headers(&self, message: &mut String) {
         for (key, value_vec) in self.headers.iter() {
             for val in value_vec {
@@ -126,6 +147,31 @@ impl Request {
     }
 }
 
+// The query is shown decoded in verbose output, with the values of repeated
+// parameters in a list
+fn serialize_query<S>(query: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let mut params: Vec<(String, Vec<String>)> = Vec::new();
+    let pairs = url::form_urlencoded::parse(query.as_deref().unwrap_or_default().as_bytes());
+    for (key, value) in pairs {
+        match params.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, values)) => values.push(value.into_owned()),
+            None => params.push((key.into_owned(), vec![value.into_owned()])),
+        }
+    }
+
+    let mut map = serializer.serialize_map(Some(params.len()))?;
+    for (key, values) in &params {
+        match values.as_slice() {
+            [value] => map.serialize_entry(key, value)?,
+            _ => map.serialize_entry(key, values)?,
+        }
+    }
+    map.end()
+}
+
 fn find_socket_addresses(url: &Url, scheme: &Scheme) -> Result<Vec<SocketAddr>, Error> {
     let mut server_details = String::new();
     match url.domain() {
@@ -143,14 +189,62 @@ fn find_socket_addresses(url: &Url, scheme: &Scheme) -> Result<Vec<SocketAddr>,
     Ok(server_details.to_socket_addrs()?.collect())
 }
 
+// Defaults are added for headers that are not given or suppressed
 fn standard_headers(input_headers: Headers, host: &str) -> Headers {
-    let mut hs = Headers::new();
-    hs.add(
-        "User-Agent",
-        &format!("{}/{}", clap::crate_name!(), clap::crate_version!()),
-    );
-    hs.add("Host", host);
-    hs.add("Connection", "close");
-    hs.append(input_headers);
-    hs
+    let mut headers = input_headers;
+    headers.suppress_empty();
+    let user_agent = format!("{}/{}", clap::crate_name!(), clap::crate_version!());
+    for (key, value) in [
+        ("User-Agent", user_agent.as_str()),
+        ("Host", host),
+        ("Connection", "close"),
+    ] {
+        if headers.get(key).is_none() && !headers.is_suppressed(key) {
+            headers.add(key, value);
+        }
+    }
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_headers() {
+        let url = Url::parse("http://127.0.0.1/animals").unwrap();
+        let mut headers = Headers::new();
+        headers.add("user-agent", "petstore-cli/2.0");
+        headers.add("Connection", "");
+        let request = Request::new(url.clone(), Method::Get, headers, None).unwrap();
+
+        assert_eq!(
+            request.headers.get("User-Agent"),
+            Some(&vec!["petstore-cli/2.0".to_string()])
+        );
+        assert!(request.headers.get("Connection").is_none());
+        assert!(request.headers.get("Host").is_some());
+
+        // Suppressed headers stay suppressed when the request is redirected
+        let redirected = Request::new(url, Method::Get, request.headers, None).unwrap();
+        assert!(redirected.headers.get("Connection").is_none());
+    }
+
+    #[test]
+    fn test_query_in_output() {
+        let url = Url::parse("http://127.0.0.1/animals?name=Monkey+D.+Luffy&crew=Zoro&crew=Nami")
+            .unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(
+            json["query"],
+            serde_json::json!({"name": "Monkey D. Luffy", "crew": ["Zoro", "Nami"]})
+        );
+        assert!(
+            String::from_utf8(request.build())
+                .unwrap()
+                .starts_with("GET /animals?name=Monkey+D.+Luffy&crew=Zoro&crew=Nami HTTP/1.1\r\n")
+        );
+    }
 }
@@ -15,6 +15,18 @@ pub struct Response {
     pub reason_phrase: String,
     pub headers: Headers,
     pub body: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<RedirectHop>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct RedirectHop {
+    pub url: String,
+    #[serde(rename = "statusCode")]
+    pub status_code: u32,
+    pub location: String,
+    #[serde(rename = "timeMs")]
+    pub time_ms: u128,
 }
 
 impl Response {
@@ -25,6 +37,15 @@ impl Response {
         let (protocol, status_code, reason_phrase) = parse_status_line(&status_line)?;
         let headers = collect_headers(head)?;
 
+        // Interim responses, such as 100 Continue, are followed by the final response
+        if (100..=199).contains(&status_code) && status_code != 101 {
+            let rest = bottom.strip_prefix(b"\r\n").unwrap_or(bottom);
+            if !rest.is_empty() {
+                log::debug!("Skipping interim response {status_code} {reason_phrase}");
+                return Response::from_buffer(rest);
+            }
+        }
+
         if (100..=199).contains(&status_code) || status_code == 204 || status_code == 304 {
             return Ok(Response {
                 protocol,
@@ -32,6 +53,7 @@ impl Response {
                 reason_phrase,
                 headers,
                 body: None,
+                redirects: Vec::new(),
             });
         }
 
@@ -55,6 +77,7 @@ impl Response {
             reason_phrase,
             headers,
             body,
+            redirects: Vec::new(),
         })
     }
 }
@@ -213,3 +236,14 @@ fn test_empty_collect_top() {
     let b = collect_head(input.as_bytes());
     assert_eq!(b.len(), 0);
 }
+
+#[test]
+fn test_interim_responses_are_skipped() {
+    let buffer = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </a.css>\r\n\r\nHTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok";
+    let response = Response::from_buffer(buffer.as_bytes()).unwrap();
+    assert_eq!(response.status_code, 201);
+    assert_eq!(response.body.as_deref(), Some("ok"));
+
+    let response = Response::from_buffer(b"HTTP/1.1 100 Continue\r\n\r\n").unwrap();
+    assert_eq!(response.status_code, 100);
+}
@@ -1,6 +1,7 @@
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::PathBuf;
 
+use crate::error;
 use crate::error::Error;
 
 pub fn read_file(path: &PathBuf) -> Result<String, Error> {
@@ -9,3 +10,52 @@ pub fn read_file(path: &PathBuf) -> Result<String, Error> {
     file.read_to_string(&mut buf)?;
     Ok(buf)
 }
+
+pub fn write_file(path: &PathBuf, content: &[u8]) -> Result<(), Error> {
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+/// Writes a file only readable by the current user, creating parent directories.
+pub fn write_private_file(path: &PathBuf, content: &[u8]) -> Result<(), Error> {
+    if let Some(parent) = path.parent()
+        && !parent.exists()
+    {
+        std::fs::DirBuilder::new().recursive(true).create(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies when the file is created, so existing files are restricted too
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+#[cfg(not(windows))]
+const TTY_PATH: &str = "/dev/tty";
+#[cfg(windows)]
+const TTY_PATH: &str = "CON";
+
+/// Asks a question on the controlling terminal rather than stdin and stdout, which
+/// may be used for the request body and the response.
+pub fn prompt(question: &str) -> Result<String, Error> {
+    let mut tty = match std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(TTY_PATH)
+    {
+        Ok(tty) => tty,
+        Err(why) => error!(&format!("can't open terminal for prompt: {why}")),
+    };
+    tty.write_all(question.as_bytes())?;
+    tty.flush()?;
+
+    let mut answer = String::new();
+    BufReader::new(tty).read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
@@ -1,8 +1,11 @@
+mod auth;
 pub mod cli;
+mod date;
 mod error;
 mod http;
 mod io;
 mod logs;
 mod modes;
 mod proxy;
+mod random;
 mod requester;
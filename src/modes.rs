@@ -1,3 +1,8 @@
+use url::Url;
+
+use crate::error;
+use crate::error::Error;
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, clap::ValueEnum)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum RedirectMode {
@@ -5,3 +10,120 @@ pub enum RedirectMode {
     NoFollow,
     Interactive,
 }
+
+/// Rules a redirect has to pass before it is followed.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RedirectPolicy {
+    /// Allow redirects from https to http.
+    pub allow_downgrade: bool,
+    /// Only follow redirects to the same host.
+    pub same_host_only: bool,
+    /// Only follow redirects to these domains and their subdomains, if not empty.
+    pub allowed_domains: Vec<String>,
+}
+
+impl RedirectPolicy {
+    pub fn check(&self, from: &Url, to: &Url) -> Result<(), Error> {
+        if !self.allow_downgrade && from.scheme() == "https" && to.scheme() == "http" {
+            error!(&format!(
+                "redirect to {to} blocked, it downgrades from https to http"
+            ))
+        }
+
+        let host = to.host_str().unwrap_or_default().to_lowercase();
+        if self.same_host_only && Some(host.as_str()) != from.host_str() {
+            error!(&format!(
+                "redirect to {to} blocked, only redirects to the same host are allowed"
+            ))
+        }
+        if !self.allowed_domains.is_empty()
+            && !self
+                .allowed_domains
+                .iter()
+                .any(|domain| domain_matches(&host, domain))
+        {
+            error!(&format!(
+                "redirect to {to} blocked, {host} is not an allowed domain"
+            ))
+        }
+
+        Ok(())
+    }
+
+    pub fn merge(self, other: RedirectPolicy) -> RedirectPolicy {
+        let mut allowed_domains = self.allowed_domains;
+        allowed_domains.extend(other.allowed_domains);
+        RedirectPolicy {
+            allow_downgrade: self.allow_downgrade || other.allow_downgrade,
+            same_host_only: self.same_host_only || other.same_host_only,
+            allowed_domains,
+        }
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    let domain = domain.trim_start_matches("*.").to_lowercase();
+    host == domain || host.ends_with(&format!(".{domain}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_downgrade_is_denied_by_default() {
+        let policy = RedirectPolicy::default();
+        let result = policy.check(&url("https://example.com"), &url("http://example.com"));
+        assert!(result.unwrap_err().message.contains("downgrades"));
+        assert!(
+            policy
+                .check(&url("http://example.com"), &url("https://example.com"))
+                .is_ok()
+        );
+
+        let policy = RedirectPolicy {
+            allow_downgrade: true,
+            ..Default::default()
+        };
+        assert!(
+            policy
+                .check(&url("https://example.com"), &url("http://example.com"))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_same_host_only() {
+        let policy = RedirectPolicy {
+            same_host_only: true,
+            ..Default::default()
+        };
+        assert!(
+            policy
+                .check(&url("https://example.com/a"), &url("https://example.com/b"))
+                .is_ok()
+        );
+        assert!(
+            policy
+                .check(&url("https://example.com"), &url("https://evil.com"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_allowed_domains() {
+        let policy = RedirectPolicy {
+            allowed_domains: vec!["example.com".to_string()],
+            ..Default::default()
+        };
+        let from = url("https://start.org");
+        assert!(policy.check(&from, &url("https://example.com")).is_ok());
+        assert!(policy.check(&from, &url("https://api.example.com")).is_ok());
+        assert!(policy.check(&from, &url("https://badexample.com")).is_err());
+    }
+}
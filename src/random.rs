@@ -0,0 +1,24 @@
+use std::hash::{BuildHasher, Hasher};
+use std::time::SystemTime;
+
+/// A random number, good enough for jitter and nonces but not for cryptography.
+pub fn random_u64() -> u64 {
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
+/// A random string of lowercase hex characters.
+pub fn random_hex(len: usize) -> String {
+    let mut hex = String::new();
+    while hex.len() < len {
+        hex.push_str(&format!("{:016x}", random_u64()));
+    }
+    hex.truncate(len);
+    hex
+}
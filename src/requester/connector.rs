@@ -1,19 +1,30 @@
 use std::io::{Read, Write};
 use std::net::{SocketAddr, TcpStream};
 
-use native_tls::TlsConnector;
+use native_tls::{TlsConnector, TlsStream};
 use std::time::Duration;
 
 use crate::error;
 use crate::error::Error;
+use crate::http::body::Body;
 
+/// A request as written to the connection.
+pub struct Message<'a> {
+    pub head: &'a [u8],
+    pub body: Option<&'a Body>,
+    /// How long to wait for `100 Continue` before sending the body, if the
+    /// request has `Expect: 100-continue`.
+    pub expect_timeout: Option<Duration>,
+}
+
+/// Sends the request head followed by the body, if any, and reads the response.
 pub trait Connector {
-    fn http_request(&self, addr: SocketAddr, request_str: &str) -> Result<Vec<u8>, Error>;
+    fn http_request(&self, addr: SocketAddr, message: &Message) -> Result<Vec<u8>, Error>;
     fn https_request(
         &self,
         addr: SocketAddr,
         domain: &str,
-        request_str: &str,
+        message: &Message,
     ) -> Result<Vec<u8>, Error>;
 }
 
@@ -28,19 +39,19 @@ impl RegularConnector {
 }
 
 impl Connector for RegularConnector {
-    fn http_request(&self, addr: SocketAddr, request_str: &str) -> Result<Vec<u8>, Error> {
-        http_request(addr, request_str, self.timeout)
+    fn http_request(&self, addr: SocketAddr, message: &Message) -> Result<Vec<u8>, Error> {
+        http_request(addr, message, self.timeout)
     }
 
     fn https_request(
         &self,
         addr: SocketAddr,
         domain: &str,
-        request_str: &str,
+        message: &Message,
     ) -> Result<Vec<u8>, Error> {
         log::debug!("Connecting to {}", addr.to_string());
         let stream = connect_timeout(&addr, self.timeout)?;
-        tls_request(stream, domain, request_str.as_bytes())
+        tls_request(stream, domain, message, self.timeout)
     }
 }
 
@@ -55,19 +66,19 @@ impl ProxyConnector {
 }
 
 impl Connector for ProxyConnector {
-    fn http_request(&self, addr: SocketAddr, request_str: &str) -> Result<Vec<u8>, Error> {
-        http_request(addr, request_str, self.timeout)
+    fn http_request(&self, addr: SocketAddr, message: &Message) -> Result<Vec<u8>, Error> {
+        http_request(addr, message, self.timeout)
     }
 
     fn https_request(
         &self,
         addr: SocketAddr,
         domain: &str,
-        request_str: &str,
+        message: &Message,
     ) -> Result<Vec<u8>, Error> {
         let mut stream = connect_timeout(&addr, self.timeout)?;
         connect_proxy(&mut stream, domain, addr)?;
-        tls_request(stream, domain, request_str.as_bytes())
+        tls_request(stream, domain, message, self.timeout)
     }
 }
 
@@ -110,29 +121,248 @@ fn connect_successful(buf: &[u8]) -> bool {
     buf.starts_with(b"HTTP/1.1 200") && buf.ends_with(b"\r\n\r\n")
 }
 
-fn tls_request(stream: TcpStream, domain: &str, request: &[u8]) -> Result<Vec<u8>, Error> {
+fn tls_request(
+    stream: TcpStream,
+    domain: &str,
+    message: &Message,
+    timeout: u64,
+) -> Result<Vec<u8>, Error> {
     let tls_connector = TlsConnector::new()?;
     let mut stream = tls_connector.connect(domain, stream)?;
     let mut response_buffer: Vec<u8> = Vec::new();
 
-    write_read(&mut stream, request, &mut response_buffer)?;
+    write_read(&mut stream, message, timeout, &mut response_buffer)?;
     Ok(response_buffer)
 }
 
-fn http_request(addr: SocketAddr, request_str: &str, timeout: u64) -> Result<Vec<u8>, Error> {
+fn http_request(addr: SocketAddr, message: &Message, timeout: u64) -> Result<Vec<u8>, Error> {
     log::debug!("Connecting to {}", addr.to_string());
     let mut stream = connect_timeout(&addr, timeout)?;
     let mut response_buffer = Vec::new();
 
-    write_read(&mut stream, request_str.as_bytes(), &mut response_buffer)?;
+    write_read(&mut stream, message, timeout, &mut response_buffer)?;
     Ok(response_buffer)
 }
 
-fn write_read<T>(stream: &mut T, message: &[u8], buffer: &mut Vec<u8>) -> Result<(), Error>
+trait Connection: Read + Write {
+    fn set_timeout(&self, timeout: Duration) -> std::io::Result<()>;
+}
+
+impl Connection for TcpStream {
+    fn set_timeout(&self, timeout: Duration) -> std::io::Result<()> {
+        self.set_read_timeout(Some(timeout))
+    }
+}
+
+impl Connection for TlsStream<TcpStream> {
+    fn set_timeout(&self, timeout: Duration) -> std::io::Result<()> {
+        self.get_ref().set_read_timeout(Some(timeout))
+    }
+}
+
+fn write_read<T>(
+    stream: &mut T,
+    message: &Message,
+    timeout: u64,
+    buffer: &mut Vec<u8>,
+) -> Result<(), Error>
 where
-    T: Write + Read,
+    T: Connection,
 {
-    stream.write_all(message)?;
+    stream.write_all(message.head)?;
+    if let Some(body) = message.body {
+        let send_body = match message.expect_timeout {
+            Some(expect_timeout) => {
+                stream.flush()?;
+                stream.set_timeout(expect_timeout)?;
+                let send_body = await_continue(stream, buffer)?;
+                stream.set_timeout(Duration::new(timeout, 0))?;
+                send_body
+            }
+            None => true,
+        };
+        if send_body {
+            body.write_encoded(stream)?;
+        }
+    }
+    stream.flush()?;
     stream.read_to_end(buffer)?;
     Ok(())
 }
+
+// Waits for the server to answer `Expect: 100-continue`. The body is sent on
+// `100 Continue` or when the wait times out, but not if the server has already
+// sent a final response, such as 401 or 413. Read responses are kept in `buffer`.
+fn await_continue<T: Read>(stream: &mut T, buffer: &mut Vec<u8>) -> Result<bool, Error> {
+    let mut chunk = [0; 1024];
+    loop {
+        match stream.read(&mut chunk) {
+            Ok(0) => return Ok(false),
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                log::debug!("No 100 Continue received, sending body");
+                return Ok(true);
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        match expect_answer(buffer) {
+            Some(100) => {
+                log::debug!("Received 100 Continue, sending body");
+                return Ok(true);
+            }
+            Some(status) if status >= 200 => {
+                log::debug!("Received {status} instead of 100 Continue, not sending body");
+                return Ok(false);
+            }
+            _ => {}
+        }
+    }
+}
+
+// The status code of the first complete response head in the buffer that
+// answers the expectation, 100 or a final status. Other interim responses are
+// skipped, and nothing after a final response head is read since it is its body.
+fn expect_answer(buffer: &[u8]) -> Option<u32> {
+    let mut rest = buffer;
+    while let Some(end) = rest.windows(4).position(|w| w == b"\r\n\r\n") {
+        let head = String::from_utf8_lossy(&rest[..end]);
+        let status = head
+            .split(' ')
+            .nth(1)
+            .and_then(|code| code.parse::<u32>().ok());
+        if let Some(status) = status
+            && (status == 100 || status >= 200)
+        {
+            return Some(status);
+        }
+        rest = &rest[end + 4..];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    const HEAD: &[u8] =
+        b"POST /upload HTTP/1.1\r\nExpect: 100-continue\r\nContent-Length: 6\r\n\r\n";
+    const EXPECT_TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_expect_answer() {
+        assert_eq!(expect_answer(b"HTTP/1.1 100 Continue\r\n\r\n"), Some(100));
+        assert_eq!(
+            expect_answer(b"HTTP/1.1 103 Early Hints\r\nLink: </a.css>\r\n\r\nHTTP/1.1 413 Content Too Large\r\n\r\n"),
+            Some(413)
+        );
+        assert_eq!(
+            expect_answer(
+                b"HTTP/1.1 413 Content Too Large\r\nContent-Length: 9\r\n\r\nbig\r\n\r\nbody"
+            ),
+            Some(413)
+        );
+        assert_eq!(expect_answer(b"HTTP/1.1 100 Cont"), None);
+    }
+
+    #[test]
+    fn test_body_is_sent_after_continue() {
+        let server = serve(|stream| {
+            read_head(stream);
+            stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").unwrap();
+            let body = read_body(stream, Duration::from_secs(5));
+            stream.write_all(b"HTTP/1.1 201 Created\r\n\r\n").unwrap();
+            body
+        });
+
+        let (response, body) = send(server);
+        assert!(response.ends_with(b"HTTP/1.1 201 Created\r\n\r\n"));
+        assert_eq!(body, b"poster");
+    }
+
+    #[test]
+    fn test_body_is_not_sent_after_final_response() {
+        let server = serve(|stream| {
+            read_head(stream);
+            stream
+                .write_all(
+                    b"HTTP/1.1 413 Content Too Large\r\nContent-Length: 9\r\n\r\nbig\r\n\r\nbody",
+                )
+                .unwrap();
+            // Waits longer than the expect timeout, to catch a body sent after it
+            read_body(stream, EXPECT_TIMEOUT * 5)
+        });
+
+        let (response, body) = send(server);
+        assert!(response.starts_with(b"HTTP/1.1 413 Content Too Large\r\n"));
+        assert!(body.is_empty());
+    }
+
+    #[test]
+    fn test_body_is_sent_after_expect_timeout() {
+        let server = serve(|stream| {
+            read_head(stream);
+            let body = read_body(stream, Duration::from_secs(5));
+            stream.write_all(b"HTTP/1.1 201 Created\r\n\r\n").unwrap();
+            body
+        });
+
+        let (response, body) = send(server);
+        assert_eq!(response, b"HTTP/1.1 201 Created\r\n\r\n");
+        assert_eq!(body, b"poster");
+    }
+
+    // Runs `handler` for one connection, returning the address and the body it read
+    fn serve(
+        handler: impl FnOnce(&mut TcpStream) -> Vec<u8> + Send + 'static,
+    ) -> (SocketAddr, JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = std::thread::spawn(move || handler(&mut listener.accept().unwrap().0));
+        (addr, handle)
+    }
+
+    fn send((addr, handle): (SocketAddr, JoinHandle<Vec<u8>>)) -> (Vec<u8>, Vec<u8>) {
+        let body = Body::from("poster");
+        let message = Message {
+            head: HEAD,
+            body: Some(&body),
+            expect_timeout: Some(EXPECT_TIMEOUT),
+        };
+        let response = RegularConnector::new(5)
+            .http_request(addr, &message)
+            .unwrap();
+        (response, handle.join().unwrap())
+    }
+
+    fn read_head(stream: &mut TcpStream) {
+        let mut head = Vec::new();
+        let mut byte = [0];
+        while !head.ends_with(b"\r\n\r\n") {
+            stream.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        assert_eq!(head, HEAD);
+    }
+
+    // Reads until the body is complete or nothing arrives within `timeout`
+    fn read_body(stream: &mut TcpStream, timeout: Duration) -> Vec<u8> {
+        stream.set_read_timeout(Some(timeout)).unwrap();
+        let mut body = Vec::new();
+        let mut chunk = [0; 64];
+        while body.len() < 6 {
+            match stream.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => body.extend_from_slice(&chunk[..read]),
+            }
+        }
+        body
+    }
+}
@@ -1,18 +1,37 @@
-use std::io::{self, Write};
+use std::collections::HashMap;
+use std::time::Instant;
 
+use crate::auth::Credentials;
+use crate::auth::digest::Challenge;
 use crate::error;
-use crate::error::Error;
-use crate::http::{Scheme, request::Request, response::Response};
+use crate::error::{Error, ErrorKind};
+use crate::http::body::Body;
+use crate::http::cookies::CookieJar;
+use crate::http::redact::redaction;
+use crate::http::response::{RedirectHop, Response};
+use crate::http::{Scheme, request::Request};
 use crate::modes::RedirectMode;
 
 pub mod connector;
+pub mod redirect;
+pub mod retry;
 
-use connector::Connector;
-use url::Url;
+use connector::{Connector, Message};
+use redirect::{PromptAnswer, RedirectOptions};
+use retry::RetryPolicy;
+
+pub const DEFAULT_MAX_REDIRECTS: u32 = 20;
+// How often a redirect may lead back to a URL, as in /a -> /login -> /a, before it is a loop
+const MAX_REVISITS: u32 = 2;
 
 pub struct Requester {
     connector: Box<dyn Connector>,
     redirect_mode: RedirectMode,
+    retry: RetryPolicy,
+    max_redirects: u32,
+    redirect_options: RedirectOptions,
+    cookie_jar: CookieJar,
+    digest: Option<Credentials>,
 }
 
 impl Requester {
@@ -20,78 +39,268 @@ impl Requester {
         Requester {
             connector,
             redirect_mode,
+            retry: RetryPolicy::none(),
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            redirect_options: RedirectOptions::default(),
+            cookie_jar: CookieJar::new(),
+            digest: None,
         }
     }
-    pub fn do_request(&self, request: Request) -> Result<Response, Error> {
-        let response = self.send_request(&request)?;
-        match self.redirect_mode {
-            RedirectMode::NoFollow => Ok(response),
-            RedirectMode::Follow | RedirectMode::Interactive => match response.status_code {
-                301 | 302 | 307 | 308 => {
-                    let location = if let Some(location) = response.headers.get_first("location") {
-                        location
-                    } else {
-                        error!("status code suggests redirect but Location header is not present")
-                    };
-                    let location_url = match Url::parse(&location) {
-                        Ok(url) => url,
-                        Err(why) => error!(&why.to_string()),
-                    };
-
-                    if let RedirectMode::Interactive = self.redirect_mode {
-                        eprint!("Redirect to {location_url}? [Y, n]: ");
-                        io::stdout().flush()?;
-                        let mut answer = String::new();
-                        match io::stdin().read_line(&mut answer) {
-                            Ok(_) => {
-                                let answer = answer.trim().to_lowercase();
-                                if answer.is_empty() && !answer.starts_with("y") {
-                                    return Ok(response);
-                                }
-                            }
-                            Err(why) => {
-                                error!(&why.to_string());
-                            }
-                        }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_max_redirects(mut self, max_redirects: u32) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    pub fn with_redirect_options(mut self, redirect_options: RedirectOptions) -> Self {
+        self.redirect_options = redirect_options;
+        self
+    }
+
+    pub fn with_cookie_jar(mut self, cookie_jar: CookieJar) -> Self {
+        self.cookie_jar = cookie_jar;
+        self
+    }
+
+    /// Answers `WWW-Authenticate: Digest` challenges from the origin of the first request.
+    pub fn with_digest_auth(mut self, credentials: Credentials) -> Self {
+        self.digest = Some(credentials);
+        self
+    }
+
+    pub fn cookie_jar(&self) -> &CookieJar {
+        &self.cookie_jar
+    }
+
+    pub fn do_request(&mut self, request: Request) -> Result<Response, Error> {
+        let mut request = request;
+        let mut redirects: Vec<RedirectHop> = Vec::new();
+        let mut interactive = matches!(self.redirect_mode, RedirectMode::Interactive);
+        let mut visits =
+            HashMap::from([((request.method.to_string(), request.url.to_string()), 1)]);
+        let origin = request.url.origin();
+        loop {
+            let started = Instant::now();
+            let mut response = self.send_with_cookies(&mut request)?;
+            let trusted = request.url.origin() == origin || self.redirect_options.location_trusted;
+            if response.status_code == 401
+                && trusted
+                && let Some(credentials) = &self.digest
+                && let Some(www_authenticate) = response.headers.get("WWW-Authenticate")
+                && let Some(challenge) = Challenge::find(www_authenticate)
+            {
+                if request.body().is_none_or(Body::can_be_sent) {
+                    log::debug!("Answering digest challenge");
+                    let authorization = challenge.authorization(
+                        credentials,
+                        &request.method.to_string().to_uppercase(),
+                        &request.target(),
+                    );
+                    request.headers.remove("Authorization");
+                    request.headers.add("Authorization", &authorization);
+                    response = self.send_with_cookies(&mut request)?;
+                } else {
+                    log::debug!(
+                        "Not answering digest challenge, the streamed body was already sent. Use --expect-continue to wait for the challenge before sending it"
+                    );
+                }
+            }
+            let elapsed = started.elapsed();
+
+            let follow = match self.redirect_mode {
+                RedirectMode::NoFollow => false,
+                RedirectMode::Follow | RedirectMode::Interactive => {
+                    redirect::is_redirect(response.status_code)
+                }
+            };
+            if !follow {
+                response.redirects = redirects;
+                return Ok(response);
+            }
+
+            let location = if let Some(location) = response.headers.get_first("location") {
+                location
+            } else {
+                error!("status code suggests redirect but Location header is not present")
+            };
+            let location_url = redirect::resolve_location(&request.url, &location)?;
+            let (method, _) = redirect::redirect_method(
+                response.status_code,
+                &request.method,
+                &self.redirect_options,
+            );
+
+            self.redirect_options
+                .policy
+                .check(&request.url, &location_url)?;
+            if redirects.len() >= self.max_redirects as usize {
+                error!(&format!(
+                    "maximum of {} redirects exceeded",
+                    self.max_redirects
+                ))
+            }
+            let visited = visits
+                .entry((method.to_string(), location_url.to_string()))
+                .or_insert(0);
+            *visited += 1;
+            if *visited > MAX_REVISITS + 1 {
+                error!(&format!("redirect loop detected at {location_url}"))
+            }
+
+            if interactive {
+                match redirect::prompt(
+                    response.status_code,
+                    &response.reason_phrase,
+                    &request.url,
+                    &location_url,
+                    &self.redirect_options,
+                )? {
+                    PromptAnswer::Follow => {}
+                    PromptAnswer::FollowAll => interactive = false,
+                    PromptAnswer::Stop => {
+                        response.redirects = redirects;
+                        return Ok(response);
                     }
+                    PromptAnswer::Abort => error!("redirect aborted"),
+                }
+            }
 
-                    log::debug!("Following redirect to {}", location_url.as_str());
-                    let request = Request::new(
-                        location_url,
-                        request.method,
-                        request.headers,
-                        Some(request.timeout),
-                    )?;
-                    self.send_request(&request)
+            log::debug!(
+                "Following redirect to {}",
+                redaction().redact_text(location_url.as_str())
+            );
+            redirects.push(RedirectHop {
+                url: request.url.to_string(),
+                status_code: response.status_code,
+                location: location_url.to_string(),
+                time_ms: elapsed.as_millis(),
+            });
+            request = redirect::redirect_request(
+                request,
+                location_url,
+                response.status_code,
+                &self.redirect_options,
+            )?;
+        }
+    }
+
+    /// Adds cookies from the jar to the request, and stores cookies from the response.
+    /// Cookie headers given by the user are kept and sent first.
+    fn send_with_cookies(&mut self, request: &mut Request) -> Result<Response, Error> {
+        let user_cookies = request.headers.remove("Cookie");
+        let mut cookies: Vec<String> = user_cookies.clone().unwrap_or_default();
+        if let Some(jar_cookies) = self.cookie_jar.header(&request.url) {
+            cookies.push(jar_cookies);
+        }
+        if !cookies.is_empty() {
+            request.headers.add("Cookie", &cookies.join("; "));
+        }
+
+        let result = self.send_with_retry(request);
+
+        request.headers.remove("Cookie");
+        for cookie in user_cookies.unwrap_or_default() {
+            request.headers.add("Cookie", &cookie);
+        }
+        if let Ok(response) = &result {
+            self.cookie_jar.store(&request.url, &response.headers);
+        }
+        result
+    }
+
+    fn send_with_retry(&self, request: &Request) -> Result<Response, Error> {
+        let started = Instant::now();
+        let mut result = self.send_request(request);
+        if !self.retry.allows_method(&request.method) {
+            return result;
+        }
+        if let Some(body) = request.body()
+            && !body.is_replayable()
+        {
+            log::debug!("Not retrying, the streamed body can't be sent again");
+            return result;
+        }
+
+        for attempt in 0..self.retry.retries {
+            if !self.retry.should_retry(&result) {
+                break;
+            }
+            let delay = match self.retry.delay(attempt, &result, started) {
+                Some(delay) => delay,
+                None => {
+                    log::debug!("Not retrying, maximum retry time would be exceeded");
+                    break;
                 }
-                _ => Ok(response),
-            },
+            };
+            log::warn!(
+                "Request failed, retrying in {} ms ({}/{})",
+                delay.as_millis(),
+                attempt + 1,
+                self.retry.retries
+            );
+            std::thread::sleep(delay);
+            result = self.send_request(request);
         }
+
+        result
     }
 
     fn send_request(&self, request: &Request) -> Result<Response, Error> {
-        let request_str = request.build();
+        let head = request.build();
+        let expect_continue = request
+            .headers
+            .get_first("Expect")
+            .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"));
+        let message = Message {
+            head: &head,
+            body: request.body(),
+            expect_timeout: expect_continue.then_some(request.expect_timeout),
+        };
+        log::debug!(
+            "{}",
+            redaction().redact_text(&format!(
+                "{} {} with headers {}",
+                request.method.to_string().to_uppercase(),
+                request.url,
+                request.headers
+            ))
+        );
+        let mut error_kind = ErrorKind::Other;
         for server in &request.servers {
             let server_str = server.to_string();
             log::debug!("Trying server {}", server_str);
             let result = match request.scheme {
-                Scheme::Http => self.connector.http_request(server.to_owned(), &request_str),
+                Scheme::Http => self.connector.http_request(server.to_owned(), &message),
                 Scheme::Https => self.connector.https_request(
                     server.to_owned(),
                     request.url.domain().unwrap(),
-                    &request_str,
+                    &message,
                 ),
             };
             match result {
-                Ok(response) => return Response::from_buffer(&response),
+                Ok(response) => {
+                    let response = Response::from_buffer(&response)?;
+                    log::debug!(
+                        "Response {} with headers {}",
+                        response.status_code,
+                        response.headers
+                    );
+                    return Ok(response);
+                }
                 Err(err) => {
                     log::warn!("Request to {} failed with error {}", server_str, err);
+                    error_kind = err.kind;
                     continue;
                 }
             }
         }
 
-        error!("no server worked for request")
+        Err(Error::with_kind("no server worked for request", error_kind))
     }
 }
 
@@ -99,7 +308,11 @@ impl Requester {
 mod tests {
     use super::*;
     use crate::{
-        http::{Method, headers::Headers},
+        http::{
+            Method,
+            body::{Body, BodyPart},
+            headers::Headers,
+        },
         requester::connector::RegularConnector,
     };
     use httptest::{Expectation, Server, matchers::*, responders::*};
@@ -122,7 +335,8 @@ mod tests {
         let url = Url::parse(&uri.to_string()).unwrap();
         let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
 
-        let requester = Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
 
         // Act
         let response = requester.do_request(request).unwrap();
@@ -143,6 +357,436 @@ mod tests {
         assert_eq!(body.age, 25);
     }
 
+    #[test]
+    fn get_request_retries_on_service_unavailable() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/flaky"))
+                .times(2)
+                .respond_with(cycle![
+                    status_code(503).append_header("Retry-After", "0"),
+                    status_code(200).body("ok"),
+                ]),
+        );
+        let url = Url::parse(&server.url("/flaky").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+
+        let mut retry = RetryPolicy::new(2);
+        retry.delay = std::time::Duration::ZERO;
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow)
+                .with_retry(retry);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body.unwrap(), "ok");
+    }
+
+    #[test]
+    fn post_request_is_not_retried() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("POST", "/flaky"))
+                .times(1)
+                .respond_with(status_code(503)),
+        );
+        let url = Url::parse(&server.url("/flaky").to_string()).unwrap();
+        let request = Request::new(url, Method::Post, Headers::new(), None).unwrap();
+
+        let mut retry = RetryPolicy::new(2);
+        retry.delay = std::time::Duration::ZERO;
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow)
+                .with_retry(retry);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 503);
+    }
+
+    #[test]
+    fn follows_multiple_redirects() {
+        // Arrange
+        let server = Server::run();
+        let second = server.url("/second").to_string();
+        let last = server.url("/last").to_string();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/first"))
+                .respond_with(status_code(302).append_header("Location", second)),
+        );
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/second"))
+                .respond_with(status_code(301).append_header("Location", last)),
+        );
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/last"))
+                .respond_with(status_code(200).body("done")),
+        );
+        let url = Url::parse(&server.url("/first").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.redirects.len(), 2);
+        assert_eq!(response.redirects[0].status_code, 302);
+        assert!(response.redirects[1].url.ends_with("/second"));
+    }
+
+    #[test]
+    fn redirect_loop_is_detected() {
+        // Arrange
+        let server = Server::run();
+        let first = server.url("/first").to_string();
+        let second = server.url("/second").to_string();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/first"))
+                .times(3)
+                .respond_with(status_code(302).append_header("Location", second)),
+        );
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/second"))
+                .times(3)
+                .respond_with(status_code(302).append_header("Location", first)),
+        );
+        let url = Url::parse(&server.url("/first").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
+
+        // Act
+        let result = requester.do_request(request);
+
+        // Assert
+        assert!(result.unwrap_err().message.contains("redirect loop"));
+    }
+
+    #[test]
+    fn redirect_back_after_login_is_followed() {
+        // Arrange
+        let server = Server::run();
+        let login = server.url("/login").to_string();
+        let animals = server.url("/animals").to_string();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/animals"),
+                request::headers(not(contains(key("cookie")))),
+            ])
+            .respond_with(status_code(302).append_header("Location", login)),
+        );
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/login")).respond_with(
+                status_code(302)
+                    .append_header("Location", animals)
+                    .append_header("Set-Cookie", "session=abc"),
+            ),
+        );
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/animals"),
+                request::headers(contains(("cookie", "session=abc"))),
+            ])
+            .respond_with(status_code(200)),
+        );
+        let url = Url::parse(&server.url("/animals").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.redirects.len(), 2);
+    }
+
+    #[test]
+    fn max_redirects_is_enforced() {
+        // Arrange
+        let server = Server::run();
+        let second = server.url("/second").to_string();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/first"))
+                .respond_with(status_code(302).append_header("Location", second)),
+        );
+        let url = Url::parse(&server.url("/first").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow)
+                .with_max_redirects(0);
+
+        // Act
+        let result = requester.do_request(request);
+
+        // Assert
+        assert!(
+            result
+                .unwrap_err()
+                .message
+                .contains("maximum of 0 redirects")
+        );
+    }
+
+    #[test]
+    fn see_other_switches_to_get_with_relative_location() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("POST", "/form"))
+                .respond_with(status_code(303).append_header("Location", "/done")),
+        );
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/done"),
+                request::body(""),
+                not(request::headers(contains(key("content-length")))),
+            ])
+            .respond_with(status_code(200)),
+        );
+        let url = Url::parse(&server.url("/form").to_string()).unwrap();
+        let request =
+            Request::with_body(url, Method::Post, Headers::new(), "name=luffy", None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 200);
+    }
+
+    #[test]
+    fn temporary_redirect_keeps_method_and_body() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("PUT", "/old"))
+                .respond_with(status_code(307).append_header("Location", "new")),
+        );
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("PUT", "/new"),
+                request::body("name=luffy"),
+            ])
+            .respond_with(status_code(204)),
+        );
+        let url = Url::parse(&server.url("/old").to_string()).unwrap();
+        let request =
+            Request::with_body(url, Method::Put, Headers::new(), "name=luffy", None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 204);
+    }
+
+    #[test]
+    fn file_body_is_streamed() {
+        // Arrange
+        let path = std::env::temp_dir().join("hur_streamed_body_test.txt");
+        std::fs::write(&path, "poster").unwrap();
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", "/upload"),
+                request::headers(contains(("content-length", "16"))),
+                request::body("wanted: poster\r\n"),
+            ])
+            .respond_with(status_code(201)),
+        );
+        let url = Url::parse(&server.url("/upload").to_string()).unwrap();
+        let body = Body::from_parts(vec![
+            BodyPart::Bytes(b"wanted: ".to_vec()),
+            BodyPart::File(path.clone()),
+            BodyPart::Bytes(b"\r\n".to_vec()),
+        ]);
+        let request = Request::with_body(url, Method::Post, Headers::new(), body, None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::NoFollow);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 201);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn stream_body_is_chunked() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", "/upload"),
+                request::headers(contains(("transfer-encoding", "chunked"))),
+                not(request::headers(contains(key("content-length")))),
+                request::body("wanted: poster"),
+            ])
+            .respond_with(status_code(201)),
+        );
+        let url = Url::parse(&server.url("/upload").to_string()).unwrap();
+        let body = Body::stream(&b"wanted: poster"[..]);
+        let request = Request::with_body(url, Method::Post, Headers::new(), body, None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::NoFollow);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 201);
+    }
+
+    #[test]
+    fn cookies_are_sent_across_redirects() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/login")).respond_with(
+                status_code(302)
+                    .append_header("Location", "/home")
+                    .append_header("Set-Cookie", "session=abc; Path=/; HttpOnly"),
+            ),
+        );
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/home"),
+                request::headers(contains(("cookie", "session=abc"))),
+            ])
+            .respond_with(status_code(200)),
+        );
+        let url = Url::parse(&server.url("/login").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::Follow);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 200);
+        assert_eq!(requester.cookie_jar().cookies().len(), 1);
+    }
+
+    #[test]
+    fn digest_challenge_is_answered() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/secret"),
+                not(request::headers(contains(key("authorization")))),
+            ])
+            .respond_with(status_code(401).append_header(
+                "WWW-Authenticate",
+                r#"Digest realm="hur", qop="auth", nonce="abc", algorithm=SHA-256"#,
+            )),
+        );
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("GET", "/secret"),
+                request::headers(contains((
+                    "authorization",
+                    matches(r#"^Digest username="luffy", realm="hur", nonce="abc", uri="/secret", algorithm=SHA-256"#)
+                ))),
+            ])
+            .respond_with(status_code(200)),
+        );
+        let url = Url::parse(&server.url("/secret").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+        let credentials = Credentials {
+            user: "luffy".to_string(),
+            password: "meat".to_string(),
+        };
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::NoFollow)
+                .with_digest_auth(credentials);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 200);
+    }
+
+    #[test]
+    fn unsupported_digest_challenge_returns_response() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/secret")).respond_with(
+                status_code(401).append_header(
+                    "WWW-Authenticate",
+                    r#"Digest realm="hur", nonce="abc", algorithm=SHA-512-256"#,
+                ),
+            ),
+        );
+        let url = Url::parse(&server.url("/secret").to_string()).unwrap();
+        let request = Request::new(url, Method::Get, Headers::new(), None).unwrap();
+        let credentials = Credentials {
+            user: "luffy".to_string(),
+            password: "meat".to_string(),
+        };
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::NoFollow)
+                .with_digest_auth(credentials);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 401);
+    }
+
+    #[test]
+    fn digest_challenge_is_not_answered_after_streamed_body() {
+        // Arrange
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", "/secret"),
+                request::body("poster"),
+            ])
+            .respond_with(status_code(401).append_header(
+                "WWW-Authenticate",
+                r#"Digest realm="hur", qop="auth", nonce="abc", algorithm=SHA-256"#,
+            )),
+        );
+        let url = Url::parse(&server.url("/secret").to_string()).unwrap();
+        let body = Body::stream(&b"poster"[..]);
+        let request = Request::with_body(url, Method::Post, Headers::new(), body, None).unwrap();
+        let credentials = Credentials {
+            user: "luffy".to_string(),
+            password: "meat".to_string(),
+        };
+        let mut requester =
+            Requester::new(Box::new(RegularConnector::new(10)), RedirectMode::NoFollow)
+                .with_digest_auth(credentials);
+
+        // Act
+        let response = requester.do_request(request).unwrap();
+
+        // Assert
+        assert_eq!(response.status_code, 401);
+    }
+
     fn get_json_server() -> Server {
         let server = Server::run();
         let responder = status_code(200)
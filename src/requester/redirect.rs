@@ -0,0 +1,378 @@
+use url::Url;
+
+use crate::error;
+use crate::error::Error;
+use crate::http::Method;
+use crate::http::headers::Headers;
+use crate::http::redact::redaction;
+use crate::http::request::Request;
+use crate::io;
+use crate::modes::RedirectPolicy;
+
+#[derive(Default)]
+pub struct RedirectOptions {
+    /// Keep POST when following a 301 instead of switching to GET.
+    pub post301: bool,
+    /// Keep POST when following a 302 instead of switching to GET.
+    pub post302: bool,
+    /// Keep credentials when redirected to another origin.
+    pub location_trusted: bool,
+    pub policy: RedirectPolicy,
+}
+
+const CREDENTIAL_HEADERS: [&str; 2] = ["Authorization", "Cookie"];
+
+// Headers added when signing with AWS Signature Version 4, only valid with the signature
+const SIGNING_HEADER_PREFIX: &str = "x-amz-";
+
+const PROMPT_QUESTION: &str = "Follow redirect? [Y]es, [n]o, follow [a]ll, [q]uit: ";
+
+pub fn is_redirect(status_code: u32) -> bool {
+    matches!(status_code, 301 | 302 | 303 | 307 | 308)
+}
+
+/// Resolves the Location header against the URL of the request that was redirected,
+/// so relative locations such as `/login` work.
+pub fn resolve_location(base: &Url, location: &str) -> Result<Url, Error> {
+    match base.join(location) {
+        Ok(url) => Ok(url),
+        Err(why) => error!(&format!("invalid redirect location \"{location}\": {why}")),
+    }
+}
+
+/// The method to use for the redirected request, and whether the body should be kept.
+pub fn redirect_method(
+    status_code: u32,
+    method: &Method,
+    options: &RedirectOptions,
+) -> (Method, bool) {
+    match (status_code, method) {
+        (303, Method::Head) => (Method::Head, false),
+        (303, _) => (Method::Get, false),
+        (301, Method::Post) if !options.post301 => (Method::Get, false),
+        (302, Method::Post) if !options.post302 => (Method::Get, false),
+        (_, method) => (method.clone(), true),
+    }
+}
+
+pub fn redirect_request(
+    request: Request,
+    location: Url,
+    status_code: u32,
+    options: &RedirectOptions,
+) -> Result<Request, Error> {
+    let (method, keep_body) = redirect_method(status_code, &request.method, options);
+    let cross_origin = is_cross_origin(&request.url, &location);
+    let body = match keep_body {
+        true => request.body().cloned(),
+        false => None,
+    };
+    if let Some(body) = &body
+        && !body.is_replayable()
+    {
+        error!(&format!(
+            "can't follow redirect to {location}, the streamed body was already sent"
+        ))
+    }
+
+    let mut headers = request.headers;
+    headers.remove("Host");
+    headers.remove("Content-Length");
+    headers.remove("Transfer-Encoding");
+    if cross_origin && !options.location_trusted {
+        // Secret headers, such as profile API keys, are credentials as well, and
+        // signing headers like the security token go with the signature
+        let secret_headers: Vec<String> = headers
+            .iter()
+            .map(|(key, _)| key.clone())
+            .filter(|key| headers.is_secret(key) || key.starts_with(SIGNING_HEADER_PREFIX))
+            .collect();
+        let removed = CREDENTIAL_HEADERS
+            .iter()
+            .map(|header| header.to_string())
+            .chain(secret_headers);
+        for header in removed {
+            if headers.remove(&header).is_some() {
+                log::debug!(
+                    "Removed {header} header on redirect to {}",
+                    redaction().redact_text(location.as_str())
+                );
+            }
+        }
+    }
+    if body.is_none() {
+        remove_content_headers(&mut headers);
+    }
+
+    let mut redirected = match body {
+        Some(body) => Request::with_body(location, method, headers, body, Some(request.timeout))?,
+        None => Request::new(location, method, headers, Some(request.timeout))?,
+    };
+    redirected.expect_timeout = request.expect_timeout;
+    Ok(redirected)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PromptAnswer {
+    Follow,
+    Stop,
+    FollowAll,
+    Abort,
+}
+
+impl PromptAnswer {
+    fn parse(answer: &str) -> Option<PromptAnswer> {
+        match answer.trim().to_lowercase().as_str() {
+            "" | "y" | "yes" | "f" | "follow" => Some(PromptAnswer::Follow),
+            "n" | "no" | "s" | "stop" => Some(PromptAnswer::Stop),
+            "a" | "all" => Some(PromptAnswer::FollowAll),
+            "q" | "quit" | "abort" => Some(PromptAnswer::Abort),
+            _ => None,
+        }
+    }
+}
+
+/// Asks on the terminal whether a redirect should be followed.
+pub fn prompt(
+    status_code: u32,
+    reason_phrase: &str,
+    from: &Url,
+    to: &Url,
+    options: &RedirectOptions,
+) -> Result<PromptAnswer, Error> {
+    // Everything is written to the terminal the answer is read from
+    let preview = preview(status_code, reason_phrase, from, to, options);
+    let mut question = format!("{}\n{PROMPT_QUESTION}", redaction().redact_text(&preview));
+    loop {
+        let answer = io::prompt(&question)?;
+        match PromptAnswer::parse(&answer) {
+            Some(answer) => return Ok(answer),
+            None => question = format!("Unknown answer \"{answer}\"\n{PROMPT_QUESTION}"),
+        }
+    }
+}
+
+fn preview(
+    status_code: u32,
+    reason_phrase: &str,
+    from: &Url,
+    to: &Url,
+    options: &RedirectOptions,
+) -> String {
+    let mut preview = format!("{status_code} {reason_phrase}, redirect to {to}");
+    if is_downgrade(from, to) {
+        preview.push_str(&format!(
+            "\nwarning: redirect downgrades from {} to {}",
+            from.scheme(),
+            to.scheme()
+        ));
+    }
+    if is_cross_origin(from, to) {
+        let credentials = match options.location_trusted {
+            true => "credentials will be sent",
+            false => "credentials will be removed",
+        };
+        preview.push_str(&format!(
+            "\nwarning: redirect to another origin {}, {credentials}",
+            to.origin().ascii_serialization()
+        ));
+    }
+    preview
+}
+
+pub fn is_downgrade(from: &Url, to: &Url) -> bool {
+    from.scheme() == "https" && to.scheme() == "http"
+}
+
+pub fn is_cross_origin(from: &Url, to: &Url) -> bool {
+    from.origin() != to.origin()
+}
+
+fn remove_content_headers(headers: &mut Headers) {
+    for header in [
+        "Content-Type",
+        "Content-Encoding",
+        "Content-Language",
+        "Content-Location",
+        "Transfer-Encoding",
+        "Expect",
+    ] {
+        headers.remove(header);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with_credentials(url: &str) -> Request {
+        let mut headers = Headers::new();
+        headers.add("Authorization", "Basic Zm9vOmJhcg==");
+        headers.add("Cookie", "session=abc");
+        headers.add("Accept", "*/*");
+        Request::new(Url::parse(url).unwrap(), Method::Get, headers, None).unwrap()
+    }
+
+    #[test]
+    fn test_parse_prompt_answer() {
+        assert_eq!(PromptAnswer::parse(""), Some(PromptAnswer::Follow));
+        assert_eq!(PromptAnswer::parse("Y"), Some(PromptAnswer::Follow));
+        assert_eq!(PromptAnswer::parse("n"), Some(PromptAnswer::Stop));
+        assert_eq!(PromptAnswer::parse("all"), Some(PromptAnswer::FollowAll));
+        assert_eq!(PromptAnswer::parse("q"), Some(PromptAnswer::Abort));
+        assert_eq!(PromptAnswer::parse("maybe"), None);
+    }
+
+    #[test]
+    fn test_preview_warnings() {
+        let from = Url::parse("https://example.com/a").unwrap();
+        let to = Url::parse("http://other.com/b").unwrap();
+        let text = preview(302, "Found", &from, &to, &RedirectOptions::default());
+        assert!(text.starts_with("302 Found, redirect to http://other.com/b"));
+        assert!(text.contains("downgrades from https to http"));
+        assert!(text.contains("another origin http://other.com, credentials will be removed"));
+
+        let same = Url::parse("https://example.com/b").unwrap();
+        let text = preview(302, "Found", &from, &same, &RedirectOptions::default());
+        assert!(!text.contains("warning"));
+    }
+
+    #[test]
+    fn test_is_cross_origin() {
+        let url = Url::parse("https://example.com/a").unwrap();
+        assert!(!is_cross_origin(
+            &url,
+            &Url::parse("https://example.com/b").unwrap()
+        ));
+        assert!(is_cross_origin(
+            &url,
+            &Url::parse("http://example.com/a").unwrap()
+        ));
+        assert!(is_cross_origin(
+            &url,
+            &Url::parse("https://example.com:8443/").unwrap()
+        ));
+        assert!(is_cross_origin(
+            &url,
+            &Url::parse("https://other.com/a").unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_cross_origin_redirect_strips_credentials() {
+        let request = request_with_credentials("http://127.0.0.1:8080/a");
+        let location = Url::parse("http://127.0.0.1:9090/b").unwrap();
+        let redirected =
+            redirect_request(request, location, 302, &RedirectOptions::default()).unwrap();
+        assert_eq!(redirected.headers.get("Authorization"), None);
+        assert_eq!(redirected.headers.get("Cookie"), None);
+        assert!(redirected.headers.get("Accept").is_some());
+    }
+
+    #[test]
+    fn test_cross_origin_redirect_strips_signing_headers() {
+        let mut request = request_with_credentials("http://127.0.0.1:8080/a");
+        request.headers.add_secret("X-Amz-Security-Token", "token");
+        request.headers.add("X-Amz-Date", "20150830T123600Z");
+        request
+            .headers
+            .add("X-Amz-Content-Sha256", "UNSIGNED-PAYLOAD");
+        let location = Url::parse("http://127.0.0.1:9090/b").unwrap();
+        let redirected =
+            redirect_request(request, location, 302, &RedirectOptions::default()).unwrap();
+        for header in ["X-Amz-Security-Token", "X-Amz-Date", "X-Amz-Content-Sha256"] {
+            assert_eq!(redirected.headers.get(header), None, "{header}");
+        }
+    }
+
+    #[test]
+    fn test_same_origin_redirect_keeps_credentials() {
+        let request = request_with_credentials("http://127.0.0.1:8080/a");
+        let location = Url::parse("http://127.0.0.1:8080/b").unwrap();
+        let redirected =
+            redirect_request(request, location, 302, &RedirectOptions::default()).unwrap();
+        assert!(redirected.headers.get("Authorization").is_some());
+        assert!(redirected.headers.get("Cookie").is_some());
+    }
+
+    #[test]
+    fn test_location_trusted_keeps_credentials() {
+        let request = request_with_credentials("http://127.0.0.1:8080/a");
+        let location = Url::parse("http://localhost:9090/b").unwrap();
+        let options = RedirectOptions {
+            location_trusted: true,
+            ..Default::default()
+        };
+        let redirected = redirect_request(request, location, 302, &options).unwrap();
+        assert!(redirected.headers.get("Authorization").is_some());
+        assert_eq!(
+            redirected.headers.get_first("Host"),
+            Some("localhost".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_relative_location() {
+        let base = Url::parse("https://example.com/a/b?c=d").unwrap();
+        assert_eq!(
+            resolve_location(&base, "/login").unwrap().as_str(),
+            "https://example.com/login"
+        );
+        assert_eq!(
+            resolve_location(&base, "next").unwrap().as_str(),
+            "https://example.com/a/next"
+        );
+        assert_eq!(
+            resolve_location(&base, "http://other.com/")
+                .unwrap()
+                .as_str(),
+            "http://other.com/"
+        );
+    }
+
+    #[test]
+    fn test_redirect_method() {
+        let options = RedirectOptions::default();
+        assert_eq!(
+            redirect_method(303, &Method::Put, &options),
+            (Method::Get, false)
+        );
+        assert_eq!(
+            redirect_method(301, &Method::Post, &options),
+            (Method::Get, false)
+        );
+        assert_eq!(
+            redirect_method(302, &Method::Post, &options),
+            (Method::Get, false)
+        );
+        assert_eq!(
+            redirect_method(307, &Method::Post, &options),
+            (Method::Post, true)
+        );
+        assert_eq!(
+            redirect_method(308, &Method::Put, &options),
+            (Method::Put, true)
+        );
+        assert_eq!(
+            redirect_method(301, &Method::Put, &options),
+            (Method::Put, true)
+        );
+    }
+
+    #[test]
+    fn test_redirect_method_keep_post() {
+        let options = RedirectOptions {
+            post301: true,
+            post302: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            redirect_method(301, &Method::Post, &options),
+            (Method::Post, true)
+        );
+        assert_eq!(
+            redirect_method(302, &Method::Post, &options),
+            (Method::Post, true)
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::date::parse_http_date;
+use crate::error;
+use crate::error::{Error, ErrorKind};
+use crate::http::Method;
+use crate::http::response::Response;
+use crate::random::random_u64;
+
+const DEFAULT_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_STATUSES: [u32; 6] = [408, 429, 500, 502, 503, 504];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RetryOn {
+    Status(u32),
+    Connect,
+    Timeout,
+}
+
+impl RetryOn {
+    pub fn try_from(retry_on: &str) -> Result<Self, Error> {
+        match retry_on.trim().to_lowercase().as_str() {
+            "connect" => Ok(RetryOn::Connect),
+            "timeout" => Ok(RetryOn::Timeout),
+            status => match status.parse::<u32>() {
+                Ok(code) if (100..=599).contains(&code) => Ok(RetryOn::Status(code)),
+                _ => error!(&format!(
+                    "invalid retry condition \"{retry_on}\", expected a status code, connect or timeout"
+                )),
+            },
+        }
+    }
+}
+
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub delay: Duration,
+    pub max_time: Option<Duration>,
+    pub retry_on: Vec<RetryOn>,
+    pub all_methods: bool,
+}
+
+impl RetryPolicy {
+    pub fn new(retries: u32) -> Self {
+        let mut retry_on = vec![RetryOn::Connect, RetryOn::Timeout];
+        retry_on.extend(DEFAULT_STATUSES.iter().map(|code| RetryOn::Status(*code)));
+        RetryPolicy {
+            retries,
+            delay: DEFAULT_DELAY,
+            max_time: None,
+            retry_on,
+            all_methods: false,
+        }
+    }
+
+    pub fn none() -> Self {
+        RetryPolicy::new(0)
+    }
+
+    pub fn allows_method(&self, method: &Method) -> bool {
+        self.all_methods || method.is_idempotent()
+    }
+
+    pub fn should_retry(&self, result: &Result<Response, Error>) -> bool {
+        match result {
+            Ok(response) => self
+                .retry_on
+                .contains(&RetryOn::Status(response.status_code)),
+            Err(err) => match err.kind {
+                ErrorKind::Connect => self.retry_on.contains(&RetryOn::Connect),
+                ErrorKind::Timeout => self.retry_on.contains(&RetryOn::Timeout),
+                ErrorKind::Other => false,
+            },
+        }
+    }
+
+    /// The delay before retry number `attempt` (starting from 0), or `None` if the
+    /// retry would exceed the maximum retry time.
+    pub fn delay(
+        &self,
+        attempt: u32,
+        result: &Result<Response, Error>,
+        started: Instant,
+    ) -> Option<Duration> {
+        let delay = match result {
+            Ok(response) => retry_after(response),
+            Err(_) => None,
+        }
+        .unwrap_or_else(|| with_jitter(self.backoff(attempt)));
+
+        if let Some(max_time) = self.max_time
+            && started.elapsed() + delay > max_time
+        {
+            return None;
+        }
+        Some(delay)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.delay.saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers.get_first("Retry-After")?;
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = parse_http_date(value)?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+// Half of the delay is kept, the other half is randomized.
+fn with_jitter(delay: Duration) -> Duration {
+    let half = delay / 2;
+    let millis = half.as_millis() as u64;
+    if millis == 0 {
+        return delay;
+    }
+    half + Duration::from_millis(random_u64() % (millis + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::headers::Headers;
+
+    fn response(status_code: u32, retry_after: Option<&str>) -> Response {
+        let mut headers = Headers::new();
+        if let Some(value) = retry_after {
+            headers.add("Retry-After", value);
+        }
+        Response {
+            protocol: "HTTP/1.1".to_string(),
+            status_code,
+            reason_phrase: String::new(),
+            headers,
+            body: None,
+            redirects: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_retry_on_try_from() {
+        assert_eq!(RetryOn::try_from("503").unwrap(), RetryOn::Status(503));
+        assert_eq!(RetryOn::try_from("connect").unwrap(), RetryOn::Connect);
+        assert_eq!(RetryOn::try_from("Timeout").unwrap(), RetryOn::Timeout);
+        assert!(RetryOn::try_from("42").is_err());
+        assert!(RetryOn::try_from("sometimes").is_err());
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::new(3);
+        assert!(policy.should_retry(&Ok(response(503, None))));
+        assert!(!policy.should_retry(&Ok(response(404, None))));
+        assert!(policy.should_retry(&Err(Error::with_kind("", ErrorKind::Timeout))));
+        assert!(!policy.should_retry(&Err(Error::new(""))));
+    }
+
+    #[test]
+    fn test_allows_method() {
+        let mut policy = RetryPolicy::new(3);
+        assert!(policy.allows_method(&Method::Get));
+        assert!(!policy.allows_method(&Method::Post));
+        policy.all_methods = true;
+        assert!(policy.allows_method(&Method::Post));
+    }
+
+    #[test]
+    fn test_retry_after_seconds() {
+        let policy = RetryPolicy::new(3);
+        let delay = policy.delay(0, &Ok(response(503, Some("7"))), Instant::now());
+        assert_eq!(delay, Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn test_retry_after_past_date() {
+        let policy = RetryPolicy::new(3);
+        let result = Ok(response(503, Some("Sun, 06 Nov 1994 08:49:37 GMT")));
+        assert_eq!(
+            policy.delay(0, &result, Instant::now()),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_backoff_with_jitter() {
+        let policy = RetryPolicy::new(3);
+        let delay = policy
+            .delay(2, &Ok(response(503, None)), Instant::now())
+            .unwrap();
+        assert!(delay >= Duration::from_secs(2) && delay <= Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_delay_exceeds_max_time() {
+        let mut policy = RetryPolicy::new(3);
+        policy.max_time = Some(Duration::from_secs(5));
+        let result = Ok(response(503, Some("10")));
+        assert_eq!(policy.delay(0, &result, Instant::now()), None);
+    }
+}